@@ -0,0 +1,558 @@
+use super::actions::{
+    Action, CalledFromCultivation, CalledFromFarmRedevelopment, CalledFromGrainUtilization,
+    CalledFromHouseRedevelopment, ConversionStage, ReturnFireplace, WithRoom, MAX_OCCUPATION_FOOD,
+};
+use super::card::{
+    anytime_exchanges, baking_capacity, harvest_exchanges, renovation_options, Renovation,
+    BAKING_IMPROVEMENTS_INDICES, MAJOR_IMPROVEMENTS_INDICES, MAX_BAKED_GRAIN, NUM_CARDS,
+    OCCUPATIONS_INDICES,
+};
+use super::farm::{Farm, Seed, NUM_FARMYARD_SPACES, ROOM_INDICES};
+use super::feeding::FeedingPlan;
+use super::fencing::{get_all_pasture_configs, Pasture, PastureConfig};
+use super::quantity::{Clay, Food, Grain, Quantity, ResourceExchange, Stone, Vegetable, Wood};
+use super::ruleset::Ruleset;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+
+// Moves without a choice, in the order of their indices
+const NUM_NO_CHOICE: usize = 8;
+// Grain or vegetable
+const NUM_CROPS: usize = 2;
+
+// The kinds of moves, in the order of their ranges in the global index
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    NoChoice,
+    UseSpace,
+    RevealRoundCard,
+    BuildRoom,
+    BuildStable,
+    Plow,
+    BuildCard,
+    GrowFamily,
+    Renovate,
+    PlayOccupation,
+    Childless,
+    Convert,
+    Fence,
+    BakeBread,
+    PayFoodOrBeg,
+    Sow,
+}
+
+const KINDS: [Kind; 16] = [
+    Kind::NoChoice,
+    Kind::UseSpace,
+    Kind::RevealRoundCard,
+    Kind::BuildRoom,
+    Kind::BuildStable,
+    Kind::Plow,
+    Kind::BuildCard,
+    Kind::GrowFamily,
+    Kind::Renovate,
+    Kind::PlayOccupation,
+    Kind::Childless,
+    Kind::Convert,
+    Kind::Fence,
+    Kind::BakeBread,
+    Kind::PayFoodOrBeg,
+    Kind::Sow,
+];
+
+// Vectors with each entry up to its cap and a total of at most `max_total`, numbered in lexicographic order
+struct BoundedVectors {
+    caps: Vec<usize>,
+    // Ways of filling the entries from i on with a total of at most r, at [i][r]
+    counts: Vec<Vec<usize>>,
+    max_total: usize,
+}
+
+impl BoundedVectors {
+    fn new(caps: Vec<usize>, max_total: usize) -> Self {
+        let mut counts = vec![vec![1; max_total + 1]; caps.len() + 1];
+        for i in (0..caps.len()).rev() {
+            for r in 0..=max_total {
+                counts[i][r] = (0..=caps[i].min(r)).map(|x| counts[i + 1][r - x]).sum();
+            }
+        }
+        Self {
+            caps,
+            counts,
+            max_total,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.counts[0][self.max_total]
+    }
+
+    fn rank(&self, vector: &[usize]) -> Option<usize> {
+        let mut ret = 0;
+        let mut left = self.max_total;
+        for (i, x) in vector.iter().enumerate() {
+            if *x > self.caps[i].min(left) {
+                return None;
+            }
+            ret += (0..*x).map(|y| self.counts[i + 1][left - y]).sum::<usize>();
+            left -= x;
+        }
+        Some(ret)
+    }
+
+    fn unrank(&self, mut idx: usize) -> Vec<usize> {
+        let mut ret = Vec::with_capacity(self.caps.len());
+        let mut left = self.max_total;
+        for i in 0..self.caps.len() {
+            let mut x = 0;
+            while idx >= self.counts[i + 1][left - x] {
+                idx -= self.counts[i + 1][left - x];
+                x += 1;
+            }
+            ret.push(x);
+            left -= x;
+        }
+        ret
+    }
+}
+
+lazy_static! {
+    // Raw crops to food, then the exchanges of the cards
+    static ref EXCHANGES: Vec<ResourceExchange> = {
+        let mut ret = raw_crop_exchanges();
+        for exchange in (0..NUM_CARDS).flat_map(anytime_exchanges) {
+            if !ret.contains(&exchange) {
+                ret.push(exchange);
+            }
+        }
+        ret
+    };
+    // Every exchange to food a family can be fed with, ordered by the good given like the exchanges of a feeding plan
+    static ref FEEDING_EXCHANGES: Vec<ResourceExchange> = {
+        let mut ret = raw_crop_exchanges();
+        for exchange in (0..NUM_CARDS).flat_map(|idx| {
+            let mut exchanges = anytime_exchanges(idx);
+            exchanges.extend(harvest_exchanges(idx));
+            exchanges
+        }) {
+            if exchange.to == Food.index() && !ret.contains(&exchange) {
+                ret.push(exchange);
+            }
+        }
+        ret.sort_by_key(|exchange| exchange.from);
+        ret
+    };
+    // Every renovation of a house of any material and size
+    static ref RENOVATIONS: Vec<Renovation> = {
+        let mut ret = Vec::new();
+        for material in [Wood.index(), Clay.index(), Stone.index()] {
+            for num_rooms in 0..=NUM_FARMYARD_SPACES {
+                for renovation in renovation_options(material, num_rooms, &[true; NUM_CARDS]) {
+                    if !ret.contains(&renovation) {
+                        ret.push(renovation);
+                    }
+                }
+            }
+        }
+        ret
+    };
+    // Every fencing of the starting farm, the fencings offered on any later farm are among them
+    static ref FENCE_CONFIGS: Vec<PastureConfig> = get_all_pasture_configs(&Farm::new().farmyard_spaces);
+    static ref FENCE_INDICES: HashMap<Vec<Pasture>, usize> = FENCE_CONFIGS
+        .iter()
+        .enumerate()
+        .map(|(i, config)| (config.pastures.clone(), i))
+        .collect();
+    // Grain baked on each baking improvement
+    static ref BAKING_ALLOCATIONS: BoundedVectors = BoundedVectors::new(
+        BAKING_IMPROVEMENTS_INDICES
+            .iter()
+            .map(|idx| baking_capacity(*idx).map_or(MAX_BAKED_GRAIN, |cap| cap.min(MAX_BAKED_GRAIN)))
+            .collect(),
+        MAX_BAKED_GRAIN,
+    );
+    // The spaces that can be fields, every space but the starting rooms
+    static ref FIELD_SPACES: Vec<usize> = (0..NUM_FARMYARD_SPACES)
+        .filter(|idx| !ROOM_INDICES.contains(idx))
+        .collect();
+    // Fields sown with grain and with vegetables
+    static ref SOWING_SPLITS: BoundedVectors =
+        BoundedVectors::new(vec![FIELD_SPACES.len(); NUM_CROPS], FIELD_SPACES.len());
+}
+
+fn raw_crop_exchanges() -> Vec<ResourceExchange> {
+    [Grain.index(), Vegetable.index()]
+        .map(|from| ResourceExchange {
+            from,
+            to: Food.index(),
+            num_from: 1,
+            num_to: 1,
+        })
+        .to_vec()
+}
+
+// Times each feeding exchange is used and the begging tokens. Every use gives at least one food and plans stop once
+// the family is fed, so together they are at most the food of the largest family, two for each member.
+fn feeding_plans(ruleset: &Ruleset) -> BoundedVectors {
+    let max_food = 2 * ruleset.max_family_members;
+    BoundedVectors::new(vec![max_food; FEEDING_EXCHANGES.len() + 1], max_food)
+}
+
+fn kind_size(kind: Kind, ruleset: &Ruleset) -> usize {
+    match kind {
+        Kind::NoChoice => NUM_NO_CHOICE,
+        Kind::UseSpace => ruleset.num_action_spaces(),
+        Kind::RevealRoundCard => ruleset.num_rounds(),
+        Kind::BuildRoom | Kind::BuildStable => NUM_FARMYARD_SPACES,
+        Kind::Plow => 2 * NUM_FARMYARD_SPACES,
+        Kind::BuildCard => 2 * MAJOR_IMPROVEMENTS_INDICES.len(),
+        Kind::GrowFamily => 2,
+        Kind::Renovate => 4 * RENOVATIONS.len(),
+        Kind::PlayOccupation => OCCUPATIONS_INDICES.len() * (MAX_OCCUPATION_FOOD + 1),
+        Kind::Childless => NUM_CROPS,
+        Kind::Convert => 2 * EXCHANGES.len(),
+        Kind::Fence => FENCE_CONFIGS.len(),
+        Kind::BakeBread => 4 * BAKING_ALLOCATIONS.len(),
+        Kind::PayFoodOrBeg => feeding_plans(ruleset).len(),
+        Kind::Sow => 4 * SOWING_SPLITS.len(),
+    }
+}
+
+/// Size of the global action index of games played with `ruleset`
+#[must_use]
+pub fn num_global_actions(ruleset: &Ruleset) -> usize {
+    KINDS.iter().map(|kind| kind_size(*kind, ruleset)).sum()
+}
+
+fn position<T: PartialEq>(items: &[T], item: &T) -> Option<usize> {
+    items.iter().position(|x| x == item)
+}
+
+fn crop(idx: usize) -> Option<usize> {
+    position(&[Grain.index(), Vegetable.index()], &idx)
+}
+
+// Two flags as a number in 0..4
+fn flags(first: bool, second: bool) -> usize {
+    2 * usize::from(first) + usize::from(second)
+}
+
+impl Action {
+    // The kind of the move and its number among the moves of that kind, None if it cannot be part of a game of `ruleset`
+    fn kind_idx(&self, ruleset: &Ruleset) -> Option<(Kind, usize)> {
+        let num_open_spaces = ruleset.num_open_spaces();
+        let farmyard_idx = |idx: usize| (idx < NUM_FARMYARD_SPACES).then_some(idx);
+        Some(match self {
+            Self::StartGame => (Kind::NoChoice, 0),
+            Self::StartRound => (Kind::NoChoice, 1),
+            Self::PlaceWorker => (Kind::NoChoice, 2),
+            Self::EndTurn => (Kind::NoChoice, 3),
+            Self::Harvest => (Kind::NoChoice, 4),
+            Self::PreHarvest => (Kind::NoChoice, 5),
+            Self::EndGame => (Kind::NoChoice, 6),
+            Self::BuildMajor => (Kind::NoChoice, 7),
+            Self::UseSpace(idx) => (
+                Kind::UseSpace,
+                (*idx < ruleset.num_action_spaces()).then_some(*idx)?,
+            ),
+            Self::RevealRoundCard(idx) => (
+                Kind::RevealRoundCard,
+                (num_open_spaces..ruleset.num_action_spaces())
+                    .contains(idx)
+                    .then(|| idx - num_open_spaces)?,
+            ),
+            Self::BuildRoom(idx) => (Kind::BuildRoom, farmyard_idx(*idx)?),
+            Self::BuildStable(idx) => (Kind::BuildStable, farmyard_idx(*idx)?),
+            Self::Plow(from, idx) => (Kind::Plow, 2 * farmyard_idx(*idx)? + usize::from(from.0)),
+            Self::BuildCard(idx, return_fireplace) => (
+                Kind::BuildCard,
+                2 * position(&MAJOR_IMPROVEMENTS_INDICES, idx)? + usize::from(return_fireplace.0),
+            ),
+            Self::GrowFamily(with_room) => (Kind::GrowFamily, usize::from(with_room.0)),
+            Self::Renovate(house, farm, renovation) => (
+                Kind::Renovate,
+                4 * position(&RENOVATIONS, renovation)? + flags(house.0, farm.0),
+            ),
+            Self::PlayOccupation(idx, food) => (
+                Kind::PlayOccupation,
+                (MAX_OCCUPATION_FOOD + 1) * position(&OCCUPATIONS_INDICES, idx)?
+                    + (*food <= MAX_OCCUPATION_FOOD).then_some(*food)?,
+            ),
+            Self::GetResourceFromChildless(res) => (Kind::Childless, crop(*res)?),
+            Self::Convert(exchange, ConversionStage::BeforePlayOccupation(cheaper)) => (
+                Kind::Convert,
+                2 * position(&EXCHANGES, exchange)? + usize::from(*cheaper),
+            ),
+            Self::Fence(config) => {
+                let i = *FENCE_INDICES.get(&config.pastures)?;
+                (Kind::Fence, (FENCE_CONFIGS[i] == *config).then_some(i)?)
+            }
+            Self::BakeBread(from, allocation) => {
+                let mut grain = [0; BAKING_IMPROVEMENTS_INDICES.len()];
+                let mut last = None;
+                for (idx, n) in allocation {
+                    let i = position(&BAKING_IMPROVEMENTS_INDICES, idx)?;
+                    if last >= Some(i) || *n == 0 {
+                        return None;
+                    }
+                    grain[i] = *n;
+                    last = Some(i);
+                }
+                (
+                    Kind::BakeBread,
+                    4 * BAKING_ALLOCATIONS.rank(&grain)? + flags(from.0, from.1),
+                )
+            }
+            Self::PayFoodOrBeg(plan) => {
+                let mut uses = vec![0; FEEDING_EXCHANGES.len() + 1];
+                let mut last = None;
+                for (exchange, times) in &plan.exchanges {
+                    let i = position(&FEEDING_EXCHANGES, exchange)?;
+                    if last >= Some(i) || *times == 0 {
+                        return None;
+                    }
+                    uses[i] = *times;
+                    last = Some(i);
+                }
+                uses[FEEDING_EXCHANGES.len()] = plan.begging_tokens;
+                (Kind::PayFoodOrBeg, feeding_plans(ruleset).rank(&uses)?)
+            }
+            Self::Sow(from, plan) => {
+                let mut split = [0; NUM_CROPS];
+                let mut last = None;
+                for (idx, seed) in plan {
+                    let i = position(&FIELD_SPACES, idx)?;
+                    if last >= Some(i) {
+                        return None;
+                    }
+                    let crop = match seed {
+                        Seed::Grain => 0,
+                        Seed::Vegetable => 1,
+                    };
+                    split[crop] += 1;
+                    last = Some(i);
+                }
+                (
+                    Kind::Sow,
+                    4 * SOWING_SPLITS.rank(&split)? + flags(from.0, from.1),
+                )
+            }
+        })
+    }
+
+    /// Index of the move in a dense range of `num_global_actions` numbers that is the same in every game with `ruleset`,
+    /// for the outputs and masks of policy networks and for compact records. The moves offered in a position have
+    /// distinct indices and every index stands for one move, which `from_global_idx` gives back without a position.
+    ///
+    /// Moves are numbered kind by kind. Within a kind the index holds every field of the move : the farmyard space,
+    /// the fencing among all fencings of the starting farm, the renovation with its cost, the grain baked on each
+    /// improvement, the times each exchange is used and the begging tokens of a feeding plan, and the flags recording
+    /// where the move was reached from. Sowings only keep how many fields get grain and vegetables, as the sowings
+    /// offered in a position differ in that alone (see `Farm::distinct_sowing_plans`), so sowings of other fields
+    /// share an index and `from_global_idx` gives the one on the first spaces that can be fields.
+    /// # Panics
+    /// If the move cannot be part of a game of `ruleset`, e.g. a farmyard space beyond the farm, a fencing that is not
+    /// one of the starting farm or a sowing plan that does not list its fields in order
+    #[must_use]
+    pub fn global_idx(&self, ruleset: &Ruleset) -> usize {
+        let (kind, idx) = self
+            .kind_idx(ruleset)
+            .unwrap_or_else(|| panic!("{self:?} has no global index"));
+        KINDS
+            .iter()
+            .take_while(|k| **k != kind)
+            .map(|k| kind_size(*k, ruleset))
+            .sum::<usize>()
+            + idx
+    }
+
+    /// Whether the move can be part of a game of `ruleset`, i.e. whether it has a global index
+    #[must_use]
+    pub fn fits_ruleset(&self, ruleset: &Ruleset) -> bool {
+        self.kind_idx(ruleset).is_some()
+    }
+
+    /// The move with the global index `idx` in games played with `ruleset`
+    /// # Errors
+    /// If `idx` is not below `num_global_actions(ruleset)`
+    pub fn from_global_idx(idx: usize, ruleset: &Ruleset) -> Result<Self, String> {
+        let mut idx_in_kind = idx;
+        for kind in KINDS {
+            let size = kind_size(kind, ruleset);
+            if idx_in_kind < size {
+                return Ok(Self::from_kind_idx(kind, idx_in_kind, ruleset));
+            }
+            idx_in_kind -= size;
+        }
+        Err(format!("No move has the index {idx}"))
+    }
+
+    fn from_kind_idx(kind: Kind, idx: usize, ruleset: &Ruleset) -> Self {
+        match kind {
+            Kind::NoChoice => [
+                Self::StartGame,
+                Self::StartRound,
+                Self::PlaceWorker,
+                Self::EndTurn,
+                Self::Harvest,
+                Self::PreHarvest,
+                Self::EndGame,
+                Self::BuildMajor,
+            ][idx]
+                .clone(),
+            Kind::UseSpace => Self::UseSpace(idx),
+            Kind::RevealRoundCard => Self::RevealRoundCard(ruleset.num_open_spaces() + idx),
+            Kind::BuildRoom => Self::BuildRoom(idx),
+            Kind::BuildStable => Self::BuildStable(idx),
+            Kind::Plow => Self::Plow(CalledFromCultivation(idx % 2 == 1), idx / 2),
+            Kind::BuildCard => Self::BuildCard(
+                MAJOR_IMPROVEMENTS_INDICES[idx / 2],
+                ReturnFireplace(idx % 2 == 1),
+            ),
+            Kind::GrowFamily => Self::GrowFamily(WithRoom(idx == 1)),
+            Kind::Renovate => Self::Renovate(
+                CalledFromHouseRedevelopment(idx % 4 >= 2),
+                CalledFromFarmRedevelopment(idx % 2 == 1),
+                RENOVATIONS[idx / 4].clone(),
+            ),
+            Kind::PlayOccupation => Self::PlayOccupation(
+                OCCUPATIONS_INDICES[idx / (MAX_OCCUPATION_FOOD + 1)],
+                idx % (MAX_OCCUPATION_FOOD + 1),
+            ),
+            Kind::Childless => {
+                Self::GetResourceFromChildless([Grain.index(), Vegetable.index()][idx])
+            }
+            Kind::Convert => Self::Convert(
+                EXCHANGES[idx / 2].clone(),
+                ConversionStage::BeforePlayOccupation(idx % 2 == 1),
+            ),
+            Kind::Fence => Self::Fence(FENCE_CONFIGS[idx].clone()),
+            Kind::BakeBread => {
+                let allocation = BAKING_ALLOCATIONS
+                    .unrank(idx / 4)
+                    .into_iter()
+                    .zip(BAKING_IMPROVEMENTS_INDICES)
+                    .filter(|(grain, _)| *grain > 0)
+                    .map(|(grain, major_idx)| (major_idx, grain))
+                    .collect();
+                Self::BakeBread(
+                    CalledFromGrainUtilization(idx % 4 >= 2, idx % 2 == 1),
+                    allocation,
+                )
+            }
+            Kind::PayFoodOrBeg => {
+                let mut uses = feeding_plans(ruleset).unrank(idx);
+                let begging_tokens = uses.pop().unwrap();
+                let exchanges = uses
+                    .into_iter()
+                    .zip(FEEDING_EXCHANGES.iter())
+                    .filter(|(times, _)| *times > 0)
+                    .map(|(times, exchange)| (exchange.clone(), times))
+                    .collect();
+                Self::PayFoodOrBeg(FeedingPlan {
+                    exchanges,
+                    begging_tokens,
+                })
+            }
+            Kind::Sow => {
+                let split = SOWING_SPLITS.unrank(idx / 4);
+                let plan = FIELD_SPACES
+                    .iter()
+                    .take(split[0] + split[1])
+                    .enumerate()
+                    .map(|(i, field_idx)| {
+                        let seed = if i < split[0] {
+                            Seed::Grain
+                        } else {
+                            Seed::Vegetable
+                        };
+                        (*field_idx, seed)
+                    })
+                    .collect();
+                Self::Sow(CalledFromGrainUtilization(idx % 4 >= 2, idx % 2 == 1), plan)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agricola::algorithms::PlayerType;
+    use crate::agricola::state::State;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_bounded_vectors() {
+        let vectors = BoundedVectors::new(vec![1, 3, 2], 3);
+        // Brute force count of the vectors up to the caps with a total of at most 3
+        let mut count = 0;
+        for a in 0..=1 {
+            for b in 0..=3 {
+                for c in 0..=2 {
+                    if a + b + c <= 3 {
+                        assert_eq!(vectors.rank(&[a, b, c]), Some(count));
+                        assert_eq!(vectors.unrank(count), vec![a, b, c]);
+                        count += 1;
+                    }
+                }
+            }
+        }
+        assert_eq!(vectors.len(), count);
+        assert_eq!(vectors.rank(&[1, 3, 0]), None);
+    }
+
+    #[test]
+    fn test_global_idx_is_a_bijection() {
+        let ruleset = Ruleset::default();
+        let num_actions = num_global_actions(&ruleset);
+        let mut start = 0;
+        for kind in KINDS {
+            let size = kind_size(kind, &ruleset);
+            // The largest kinds are checked on a sample of their indices
+            let step = (size / 20_000).max(1);
+            for idx in (start..start + size)
+                .step_by(step)
+                .chain([start + size - 1])
+            {
+                let action = Action::from_global_idx(idx, &ruleset).unwrap();
+                assert_eq!(action.global_idx(&ruleset), idx, "{action:?}");
+            }
+            start += size;
+        }
+        assert_eq!(start, num_actions);
+        assert!(Action::from_global_idx(num_actions, &ruleset).is_err());
+    }
+
+    #[test]
+    fn test_global_idx_round_trip() {
+        let mut rng = ChaCha8Rng::seed_from_u64(31);
+        for seed in 0..2 {
+            let mut state = State::with_seed(&[PlayerType::Human; 3], seed).unwrap();
+            let ruleset = state.ruleset().clone();
+            loop {
+                let choices = Action::next_choices(&state);
+                if choices.is_empty() {
+                    break;
+                }
+                let mut seen = HashSet::new();
+                for (action, _) in &choices {
+                    let idx = action.global_idx(&ruleset);
+                    assert!(idx < num_global_actions(&ruleset));
+                    assert!(seen.insert(idx), "{action:?} shares its index");
+                    let decoded = Action::from_global_idx(idx, &ruleset).unwrap();
+                    // Sowings are decoded on the first spaces that can be fields
+                    if matches!(action, Action::Sow(..)) {
+                        assert_eq!(decoded.global_idx(&ruleset), idx);
+                    } else {
+                        assert_eq!(&decoded, action);
+                    }
+                }
+                let action = &choices[rng.gen_range(0..choices.len())].0;
+                action.apply_choice(&mut state);
+            }
+        }
+    }
+}
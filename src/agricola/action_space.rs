@@ -1,9 +1,6 @@
-use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-use super::quantity::{
-    Boar, Cattle, Clay, Food, Grain, Quantities, Quantity, Reed, Resources, Sheep, Stone,
-    Vegetable, Wood,
-};
+use super::quantity::{new_res, Boar, Cattle, Quantities, Quantity, Resources, Sheep};
 
 pub fn take_resources(player_quantities: &mut Quantities, resources: &Resources) {
     for (a, b) in resources.iter().zip(player_quantities.iter_mut()) {
@@ -11,337 +8,207 @@ pub fn take_resources(player_quantities: &mut Quantities, resources: &Resources)
     }
 }
 
-pub trait ActionSpace {
-    fn index(&self) -> usize;
-}
-
-pub struct Copse;
-pub struct Grove;
-pub struct Forest;
-pub struct ResourceMarket;
-pub struct Hollow;
-pub struct ClayPit;
-pub struct ReedBank;
-pub struct TravelingPlayers;
-pub struct Fishing;
-pub struct DayLaborer;
-pub struct GrainSeeds;
-pub struct MeetingPlace;
-pub struct Farmland;
-pub struct FarmExpansion;
-pub struct Lessons1;
-pub struct Lessons2;
-pub struct SheepMarket;
-pub struct GrainUtilization;
-pub struct Fencing;
-pub struct Improvements;
-pub struct WishForChildren;
-pub struct WesternQuarry;
-pub struct HouseRedevelopment;
-pub struct PigMarket;
-pub struct VegetableSeeds;
-pub struct EasternQuarry;
-pub struct CattleMarket;
-pub struct Cultivation;
-pub struct UrgentWishForChildren;
-pub struct FarmRedevelopment;
-
-impl ActionSpace for Copse {
-    fn index(&self) -> usize {
-        0
-    }
-}
-
-impl ActionSpace for Grove {
-    fn index(&self) -> usize {
-        1
-    }
-}
-
-impl ActionSpace for Forest {
-    fn index(&self) -> usize {
-        2
-    }
-}
-
-impl ActionSpace for ResourceMarket {
-    fn index(&self) -> usize {
-        3
-    }
-}
-
-impl ActionSpace for Hollow {
-    fn index(&self) -> usize {
-        4
-    }
-}
-
-impl ActionSpace for ClayPit {
-    fn index(&self) -> usize {
-        5
-    }
-}
-
-impl ActionSpace for ReedBank {
-    fn index(&self) -> usize {
-        6
-    }
-}
-
-impl ActionSpace for TravelingPlayers {
-    fn index(&self) -> usize {
-        7
-    }
-}
-
-impl ActionSpace for Fishing {
-    fn index(&self) -> usize {
-        8
-    }
-}
-
-impl ActionSpace for DayLaborer {
-    fn index(&self) -> usize {
-        9
-    }
-}
-
-impl ActionSpace for GrainSeeds {
-    fn index(&self) -> usize {
-        10
-    }
-}
-
-impl ActionSpace for MeetingPlace {
-    fn index(&self) -> usize {
-        11
-    }
-}
-
-impl ActionSpace for Farmland {
-    fn index(&self) -> usize {
-        12
-    }
-}
-
-impl ActionSpace for FarmExpansion {
-    fn index(&self) -> usize {
-        13
-    }
-}
-
-impl ActionSpace for Lessons1 {
-    fn index(&self) -> usize {
-        14
-    }
-}
-
-impl ActionSpace for Lessons2 {
-    fn index(&self) -> usize {
-        15
-    }
-}
-
-impl ActionSpace for SheepMarket {
-    fn index(&self) -> usize {
-        16
-    }
-}
-
-impl ActionSpace for GrainUtilization {
-    fn index(&self) -> usize {
-        17
-    }
-}
-
-impl ActionSpace for Fencing {
-    fn index(&self) -> usize {
-        18
-    }
-}
-
-impl ActionSpace for Improvements {
-    fn index(&self) -> usize {
-        19
-    }
-}
-
-impl ActionSpace for WishForChildren {
-    fn index(&self) -> usize {
-        20
-    }
-}
-
-impl ActionSpace for WesternQuarry {
-    fn index(&self) -> usize {
-        21
-    }
-}
-
-impl ActionSpace for HouseRedevelopment {
-    fn index(&self) -> usize {
-        22
-    }
-}
-
-impl ActionSpace for PigMarket {
-    fn index(&self) -> usize {
-        23
-    }
-}
-
-impl ActionSpace for VegetableSeeds {
-    fn index(&self) -> usize {
-        24
-    }
-}
-
-impl ActionSpace for EasternQuarry {
-    fn index(&self) -> usize {
-        25
-    }
-}
-
-impl ActionSpace for CattleMarket {
-    fn index(&self) -> usize {
-        26
-    }
-}
-
-impl ActionSpace for Cultivation {
-    fn index(&self) -> usize {
-        27
-    }
-}
-
-impl ActionSpace for UrgentWishForChildren {
-    fn index(&self) -> usize {
-        28
-    }
-}
-
-impl ActionSpace for FarmRedevelopment {
-    fn index(&self) -> usize {
-        29
-    }
-}
-
-pub const OPEN_SPACES: usize = 16;
-pub const NUM_ACTION_SPACES: usize = 30;
-pub const ACCUMULATION_SPACE_INDICES: [usize; 13] = [0, 1, 2, 4, 5, 6, 7, 8, 16, 21, 23, 25, 26];
-pub const RESOURCE_SPACE_INDICES: [usize; 5] = [3, 9, 10, 11, 24];
-
-pub const ACTION_SPACE_NAMES: [&str; NUM_ACTION_SPACES] = [
-    "Copse",
-    "Grove",
-    "Forest",
-    "Resource Market",
-    "Hollow",
-    "Clay Pit",
-    "Reed Bank",
-    "Traveling Players",
-    "Fishing",
-    "Day Laborer",
-    "Grain Seeds",
-    "Meeting Place",
-    "Farmland",
-    "Farm Expansion",
-    "Lessons(1)",
-    "Lessons(2)",
-    "Sheep Market",
-    "Grain Utilization",
-    "Fencing",
-    "Improvements",
-    "Wish For Children",
-    "Western Quarry",
-    "House Redevelopment",
-    "Pig Market",
-    "Vegetable Seeds",
-    "Eastern Quarry",
-    "Cattle Market",
-    "Cultivation",
-    "Urgent Wish For Children",
-    "Farm Redevelopment",
-];
-
-pub fn accumulate(idx: usize, res: &mut Resources) {
-    match idx {
-        0 => {
-            res[Wood.index()] += 1;
-        }
-        1 => {
-            res[Wood.index()] += 2;
-        }
-        2 => {
-            res[Wood.index()] += 3;
-        }
-        4 => {
-            res[Clay.index()] += 2;
-        }
-        5 => {
-            res[Clay.index()] += 1;
-        }
-        6 => {
-            res[Reed.index()] += 1;
-        }
-        7 | 8 => {
-            res[Food.index()] += 1;
-        }
-        16 => {
-            res[Sheep.index()] += 1;
-        }
-        21 | 25 => {
-            res[Stone.index()] += 1;
-        }
-        23 => {
-            res[Boar.index()] += 1;
-        }
-        26 => {
-            res[Cattle.index()] += 1;
-        }
-        _ => {}
-    }
-}
-
-pub fn get_resource(idx: usize, res: &mut Quantities) {
-    match idx {
-        3 => {
-            res[Food.index()] += 1;
-            res[Stone.index()] += 1;
-            res[Reed.index()] += 1;
-        }
-        9 => {
-            res[Food.index()] += 2;
-        }
-        10 => {
-            res[Grain.index()] += 1;
-        }
-        11 => {
-            res[Food.index()] += 1;
-        }
-        24 => {
-            res[Vegetable.index()] += 1;
-        }
-        _ => {}
-    }
-}
-
-pub fn randomize_action_spaces(sequence: &mut [usize], round: usize) {
-    let mut rng = rand::thread_rng();
-    if round == 1 || round == 2 || round == 3 {
-        let next_idx = rng.gen_range(round - 1..4);
-        sequence.swap(OPEN_SPACES + round - 1, OPEN_SPACES + next_idx);
-    } else if round == 5 || round == 6 {
-        let next_idx = rng.gen_range(round - 1..7);
-        sequence.swap(OPEN_SPACES + round - 1, OPEN_SPACES + next_idx);
-    } else if round == 8 {
-        let next_idx = rng.gen_range(7..9);
-        sequence.swap(OPEN_SPACES + round - 1, OPEN_SPACES + next_idx);
-    } else if round == 10 {
-        let next_idx = rng.gen_range(9..11);
-        sequence.swap(OPEN_SPACES + round - 1, OPEN_SPACES + next_idx);
-    } else if round == 12 {
-        let next_idx = rng.gen_range(11..13);
-        sequence.swap(OPEN_SPACES + round - 1, OPEN_SPACES + next_idx);
+/// What a player does on an action space after collecting its goods
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FollowUp {
+    #[default]
+    None,
+    StartingPlayer,
+    DayLaborer,
+    Plow,
+    FarmExpansion,
+    Lessons {
+        cheaper: bool,
+    },
+    GrainUtilization,
+    Fencing,
+    Improvements,
+    GrowFamily {
+        with_room: bool,
+    },
+    HouseRedevelopment,
+    Cultivation,
+    FarmRedevelopment,
+}
+
+/// An action space as data. Stage 0 spaces are open from the start, the others are round cards of that stage.
+/// `accumulation` is added to the space every round and taken all at once, `fixed` is received on every use.
+/// Goods are written as a map from resource names to amounts, leaving out the resources that are not given.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActionSpaceDef {
+    pub name: String,
+    pub stage: usize,
+    #[serde(default = "new_res", with = "goods")]
+    pub accumulation: Resources,
+    #[serde(default = "new_res", with = "goods")]
+    pub fixed: Resources,
+    #[serde(default)]
+    pub follow_up: FollowUp,
+}
+
+impl ActionSpaceDef {
+    #[must_use]
+    pub fn is_accumulation(&self) -> bool {
+        self.accumulation.iter().any(|x| *x > 0)
+    }
+
+    #[must_use]
+    pub fn gives_animals(&self) -> bool {
+        [Sheep.index(), Boar.index(), Cattle.index()]
+            .iter()
+            .any(|idx| self.accumulation[*idx] + self.fixed[*idx] > 0)
+    }
+
+    pub fn accumulate(&self, res: &mut Resources) {
+        for (a, b) in self.accumulation.iter().zip(res.iter_mut()) {
+            *b += *a;
+        }
+    }
+}
+
+mod goods {
+    use super::super::quantity::{new_res, Resources, RESOURCE_NAMES};
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::BTreeMap;
+
+    pub fn serialize<S: Serializer>(res: &Resources, serializer: S) -> Result<S::Ok, S::Error> {
+        let map: BTreeMap<&str, usize> = RESOURCE_NAMES
+            .iter()
+            .zip(res)
+            .filter(|(_, amount)| **amount > 0)
+            .map(|(name, amount)| (*name, *amount))
+            .collect();
+        map.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Resources, D::Error> {
+        let map = BTreeMap::<String, usize>::deserialize(deserializer)?;
+        let mut res = new_res();
+        for (name, amount) in map {
+            let idx = RESOURCE_NAMES
+                .iter()
+                .position(|x| *x == name)
+                .ok_or_else(|| D::Error::custom(format!("Unknown resource {name}")))?;
+            res[idx] = amount;
+        }
+        Ok(res)
+    }
+}
+
+/// The spaces of the base game, open spaces first and then the round cards ordered by stage
+/// # Panics
+/// Never, the standard spaces are checked by the tests
+#[must_use]
+pub fn standard_action_spaces() -> Vec<ActionSpaceDef> {
+    serde_json::from_str(include_str!("action_spaces.json")).unwrap()
+}
+
+/// # Errors
+/// If there are no open spaces or no round cards, or the round cards are not ordered by stage
+pub fn validate_action_spaces(spaces: &[ActionSpaceDef]) -> Result<(), String> {
+    let num_open = num_open_spaces(spaces);
+    if num_open == 0 || num_open == spaces.len() {
+        return Err(String::from(
+            "There must be open action spaces and round cards",
+        ));
+    }
+    if let Some(pair) = spaces.windows(2).find(|w| w[0].stage > w[1].stage) {
+        return Err(format!(
+            "{} comes after {}, action spaces must be ordered by stage",
+            pair[1].name, pair[0].name
+        ));
+    }
+    Ok(())
+}
+
+/// Spaces open from the start, they come first in the list of spaces
+#[must_use]
+pub fn num_open_spaces(spaces: &[ActionSpaceDef]) -> usize {
+    spaces.iter().filter(|space| space.stage == 0).count()
+}
+
+/// Round cards that can be revealed for `round` : the unrevealed cards of the stage the round belongs to
+#[must_use]
+pub fn round_card_candidates(
+    spaces: &[ActionSpaceDef],
+    sequence: &[usize],
+    round: usize,
+) -> Vec<usize> {
+    let slot = num_open_spaces(spaces) + round - 1;
+    let stage = spaces[sequence[slot]].stage;
+    sequence[slot..]
+        .iter()
+        .take_while(|&&idx| spaces[idx].stage == stage)
+        .copied()
+        .collect()
+}
+
+/// For each round after `current_round`, the round cards it may reveal and their probabilities.
+/// A future round can reveal any unrevealed card of its stage, all equally likely.
+#[must_use]
+pub fn upcoming_round_cards(
+    spaces: &[ActionSpaceDef],
+    sequence: &[usize],
+    current_round: usize,
+) -> Vec<(usize, Vec<(usize, f64)>)> {
+    let open_spaces = num_open_spaces(spaces);
+    let num_rounds = sequence.len() - open_spaces;
+    (current_round + 1..=num_rounds)
+        .map(|round| {
+            let stage = spaces[sequence[open_spaces + round - 1]].stage;
+            let unrevealed = sequence[open_spaces + current_round..]
+                .iter()
+                .filter(|&&idx| spaces[idx].stage == stage)
+                .copied()
+                .collect::<Vec<usize>>();
+            #[allow(clippy::cast_precision_loss)]
+            let probability = 1.0 / unrevealed.len() as f64;
+            let odds = unrevealed
+                .into_iter()
+                .map(|idx| (idx, probability))
+                .collect();
+            (round, odds)
+        })
+        .collect()
+}
+
+/// # Panics
+/// If `card_idx` cannot be revealed for `round`
+pub fn reveal_round_card(
+    spaces: &[ActionSpaceDef],
+    sequence: &mut [usize],
+    round: usize,
+    card_idx: usize,
+) {
+    assert!(round_card_candidates(spaces, sequence, round).contains(&card_idx));
+    let slot = num_open_spaces(spaces) + round - 1;
+    let pos = sequence.iter().position(|&idx| idx == card_idx).unwrap();
+    sequence.swap(slot, pos);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_standard_action_spaces() {
+        let spaces = standard_action_spaces();
+        assert_eq!(spaces.len(), 30);
+        assert_eq!(num_open_spaces(&spaces), 16);
+        assert!(validate_action_spaces(&spaces).is_ok());
+        assert_eq!(spaces[16].name, "Sheep Market");
+        assert!(spaces[16].gives_animals());
+
+        // Goods left out of the data are zero and are left out again when written
+        let json = serde_json::to_string(&spaces).unwrap();
+        assert!(!json.contains("\"Cattle\":0"));
+        let parsed: Vec<ActionSpaceDef> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, spaces);
+
+        assert!(serde_json::from_str::<ActionSpaceDef>(
+            r#"{ "name": "Mine", "stage": 2, "accumulation": { "Gold": 1 } }"#
+        )
+        .is_err());
     }
 }
@@ -1,93 +1,62 @@
-use super::action_space::{
-    get_resource, take_resources, ActionSpace, CattleMarket, ClayPit, Copse, Cultivation,
-    DayLaborer, FarmExpansion, FarmRedevelopment, Farmland, Fencing, Fishing, Forest,
-    GrainUtilization, Grove, Hollow, HouseRedevelopment, Improvements, Lessons1, Lessons2,
-    PigMarket, ReedBank, ResourceMarket, SheepMarket, TravelingPlayers, UrgentWishForChildren,
-    WishForChildren, ACCUMULATION_SPACE_INDICES, OPEN_SPACES, RESOURCE_SPACE_INDICES,
-};
+use super::action_space::FollowUp;
 use super::card::{
-    anytime_exchanges, cost, harvest_exchanges, AssistantTiller, BasketmakersWorkshop, Card,
-    Childless, ClayOven, CookingHearth1, CookingHearth2, Fireplace1, Fireplace2, Joinery, Pottery,
-    StoneOven, BAKING_IMPROVEMENTS_INDICES, CARD_NAMES, MAJOR_IMPROVEMENTS_INDICES,
+    allocation_food, allocation_grain, anytime_exchanges, best_baking_allocation, cost,
+    AssistantTiller, BakingAllocation, Card, Childless, ClayOven, CookingHearth1, CookingHearth2,
+    Fireplace1, Fireplace2, Renovation, StoneOven, CARD_NAMES, MAJOR_IMPROVEMENTS_INDICES,
+    MAX_BAKED_GRAIN, OCCUPATIONS_INDICES,
 };
-use super::display::RESOURCE_EMOJIS;
-use super::farm::Seed;
+use super::display::{format_resources, RESOURCE_EMOJIS};
+use super::farm::SowingPlan;
+use super::feeding::FeedingPlan;
 use super::fencing::PastureConfig;
-use super::flag::{BeforeRoundStart, Flag, UsedBasketmakersWorkshop, UsedJoinery, UsedPottery};
+use super::flag::{BeforeRoundStart, Flag};
 use super::quantity::{
-    can_pay_for_resource, new_res, Boar, Cattle, Clay, Food, Grain, Quantity, Reed,
-    ResourceExchange, Sheep, Stone, Vegetable, Wood,
+    can_pay_for_resource, new_res, pay_for_resource, Food, Grain, Quantity, ResourceExchange,
+    Resources, Vegetable,
 };
 use super::state::State;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::fmt::Display;
 use std::fmt::Formatter;
 use std::hash::Hash;
 pub const NUM_RESOURCE_SPACES: usize = 18;
+/// Most food an occupation costs
+pub const MAX_OCCUPATION_FOOD: usize = 2;
 
-// Tuple <called from grain utilization, baked bread already>
-#[derive(Debug, Clone, Hash)]
-pub struct CalledFromGrainUtilization(bool, bool);
-
-#[derive(Debug, Clone, Hash)]
-pub struct CalledFromHouseRedevelopment(bool);
+// Tuple <called from grain utilization, the other half of grain utilization (sowing or baking) done already>
+#[derive(Debug, Clone, Hash, PartialEq, Serialize, Deserialize)]
+pub struct CalledFromGrainUtilization(pub(crate) bool, pub(crate) bool);
 
-#[derive(Debug, Clone, Hash)]
-pub struct CalledFromFarmRedevelopment(bool);
+#[derive(Debug, Clone, Hash, PartialEq, Serialize, Deserialize)]
+pub struct CalledFromHouseRedevelopment(pub(crate) bool);
 
-#[derive(Debug, Clone, Hash)]
-pub struct NumGrainToBake(usize);
+#[derive(Debug, Clone, Hash, PartialEq, Serialize, Deserialize)]
+pub struct CalledFromFarmRedevelopment(pub(crate) bool);
 
-#[derive(Debug, Clone, Hash)]
-pub struct ReturnFireplace(bool);
+#[derive(Debug, Clone, Hash, PartialEq, Serialize, Deserialize)]
+pub struct ReturnFireplace(pub(crate) bool);
 
-#[derive(Debug, Clone, Hash)]
-pub struct WithRoom(bool);
+#[derive(Debug, Clone, Hash, PartialEq, Serialize, Deserialize)]
+pub struct WithRoom(pub(crate) bool);
 
-#[derive(Debug, Clone, Hash)]
-pub struct CalledFromCultivation(bool);
+#[derive(Debug, Clone, Hash, PartialEq, Serialize, Deserialize)]
+pub struct CalledFromCultivation(pub(crate) bool);
 
-#[derive(Debug, Clone, Hash)]
+#[derive(Debug, Clone, Hash, PartialEq, Serialize, Deserialize)]
 pub struct UsedOven(bool);
 
-#[derive(Debug, Clone, Hash)]
+#[derive(Debug, Clone, Hash, PartialEq, Serialize, Deserialize)]
 pub enum ConversionStage {
-    Harvest,
     BeforePlayOccupation(bool),
 }
 
-#[derive(Clone, Hash)]
+#[derive(Clone, Hash, PartialEq, Serialize, Deserialize)]
 pub enum Action {
-    UseCopse,
-    UseGrove,
-    UseForest,
-    UseResourceMarket,
-    UseHollow,
-    UseClayPit,
-    UseReedBank,
-    UseTravelingPlayers,
-    UseFishing,
-    UseDayLaborer,
-    UseGrainSeeds,
-    UseMeetingPlace,
-    UseSheepMarket,
-    UseWesternQuarry,
-    UsePigMarket,
-    UseVegetableSeeds,
-    UseEasternQuarry,
-    UseCattleMarket,
-    UseFarmland,
-    UseFarmExpansion,
-    UseLessons(bool),
-    UseGrainUtilization,
-    UseFencing,
-    UseImprovements,
-    UseWishForChildren,
-    UseHouseRedevelopment,
-    UseCultivation,
-    UseUrgentWishForChildren,
-    UseFarmRedevelopment,
+    UseSpace(usize), // Action space index
     StartRound,
+    RevealRoundCard(usize), // Action space index of the round card
     PlaceWorker,
     BuildRoom(usize),
     BuildStable(usize),
@@ -96,15 +65,19 @@ pub enum Action {
     EndTurn,
     EndGame,
     BuildMajor,
-    BakeBread(CalledFromGrainUtilization, NumGrainToBake),
-    Sow(CalledFromGrainUtilization, Seed),
-    Renovate(CalledFromHouseRedevelopment, CalledFromFarmRedevelopment),
+    BakeBread(CalledFromGrainUtilization, BakingAllocation),
+    Sow(CalledFromGrainUtilization, SowingPlan),
+    Renovate(
+        CalledFromHouseRedevelopment,
+        CalledFromFarmRedevelopment,
+        Renovation,
+    ),
     GrowFamily(WithRoom),
     Fence(PastureConfig),
     Plow(CalledFromCultivation, usize),
-    Convert(ResourceExchange, Option<usize>, ConversionStage), // Exchange, harvest flag index, stage
+    Convert(ResourceExchange, ConversionStage),
     PreHarvest,
-    PayFoodOrBeg,
+    PayFoodOrBeg(FeedingPlan),
     StartGame,
     PlayOccupation(usize, usize),    // Occ index and food required
     GetResourceFromChildless(usize), // index of Grain or Vegetable
@@ -115,6 +88,43 @@ const DEFAULT_WEIGHT: f64 = 1.0;
 const ZERO_WEIGHT: f64 = 0.0;
 pub type WeightedAction = (Action, f64);
 
+/// Why an action was refused by `State::try_apply`
+#[derive(Clone, Debug, PartialEq)]
+pub enum RuleError {
+    /// The game has ended, no more actions can be applied
+    GameOver,
+    /// The action is not one of the choices in the current state
+    IllegalAction(Action),
+    /// The player cannot pay these resources
+    NotEnoughResources(Resources),
+    /// The action space is taken by another worker this round
+    ActionSpaceOccupied(usize),
+    /// The farmyard space is already used for something the action cannot be done on
+    SpaceOccupied(usize),
+    /// There is no farmyard space with this index
+    InvalidFarmyardIndex(usize),
+    /// Every stable the rules allow is already built
+    NoStablesLeft,
+}
+
+impl Display for RuleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::GameOver => write!(f, "The game is over"),
+            Self::IllegalAction(action) => write!(f, "{action:?} is not a legal action here"),
+            Self::NotEnoughResources(cost) => {
+                write!(f, "Not enough resources to pay{}", format_resources(cost))
+            }
+            Self::ActionSpaceOccupied(idx) => write!(f, "Action space {idx} is occupied"),
+            Self::SpaceOccupied(idx) => write!(f, "Farmyard space {idx} is already in use"),
+            Self::InvalidFarmyardIndex(idx) => write!(f, "There is no farmyard space {idx}"),
+            Self::NoStablesLeft => write!(f, "There are no stables left to build"),
+        }
+    }
+}
+
+impl std::error::Error for RuleError {}
+
 impl Action {
     #[allow(clippy::too_many_lines)]
     #[must_use]
@@ -122,93 +132,54 @@ impl Action {
         let mut ret: Vec<WeightedAction> = Vec::new();
         match &state.last_action {
             Self::GetResourceFromChildless(_res) => vec![(Self::PlaceWorker, DEFAULT_WEIGHT)],
-            Self::UseLessons(cheaper) => Self::occupation_choices(state, *cheaper),
             Self::EndGame => vec![],
             Self::StartGame => vec![(Self::StartRound, DEFAULT_WEIGHT)],
-            Self::StartRound => vec![(Self::PlaceWorker, DEFAULT_WEIGHT)],
+            Self::StartRound => state.chance_outcomes(),
+            Self::RevealRoundCard(_) => vec![(Self::PlaceWorker, DEFAULT_WEIGHT)],
             Self::PlaceWorker => Self::place_worker_choices(state),
-            Self::UseFarmland => {
-                let field_opt = state.next_field_position();
-                if let Some(field_idx) = field_opt {
-                    ret.push((
-                        Self::Plow(CalledFromCultivation(false), field_idx),
-                        DEFAULT_WEIGHT,
-                    ));
-                }
-                ret
+            Self::UseSpace(idx) => {
+                Self::follow_up_choices(state, state.action_space(*idx).follow_up)
             }
-            Self::UseFarmExpansion => Self::farm_expansion_choices(state),
-            Self::UseFencing => Self::fencing_choices(state),
-            Self::UseGrainUtilization => Self::grain_utilization_choices(state, false),
             Self::BuildRoom(_) | Self::BuildStable(_) => {
                 ret.extend(Self::farm_expansion_choices(state));
                 ret.push((Self::EndTurn, DEFAULT_WEIGHT));
                 ret
             }
-            Self::Sow(called_from_grain_util, _seed) => {
-                if called_from_grain_util.0 {
-                    ret.extend(Self::grain_utilization_choices(
+            Self::Sow(called_from_grain_util, _plan) => {
+                if called_from_grain_util.0 && !called_from_grain_util.1 {
+                    ret.extend(Self::baking_choices(
                         state,
-                        called_from_grain_util.1,
+                        &CalledFromGrainUtilization(true, true),
                     ));
-                } else {
-                    ret.extend(Self::sow_choices(state, called_from_grain_util));
                 }
                 ret.push((Self::EndTurn, DEFAULT_WEIGHT));
                 ret
             }
-            Self::UseImprovements => vec![(Self::BuildMajor, DEFAULT_WEIGHT)], // TODO : Add BuildMinor here
             Self::BuildMajor => Self::build_major_choices(state),
             Self::BuildCard(idx, _) => {
                 if *idx == ClayOven.index() || *idx == StoneOven.index() {
-                    ret.extend(Self::baking_choices(state, false));
+                    ret.extend(Self::baking_choices(
+                        state,
+                        &CalledFromGrainUtilization(false, false),
+                    ));
                 }
                 ret.push((Self::EndTurn, DEFAULT_WEIGHT));
                 ret
             }
-            Self::BakeBread(called_from_grain_util, _num_grain_to_bake) => {
-                if called_from_grain_util.0 {
-                    ret.extend(Self::grain_utilization_choices(state, true));
+            Self::BakeBread(called_from_grain_util, _allocation) => {
+                if called_from_grain_util.0 && !called_from_grain_util.1 {
+                    ret.extend(Self::sow_choices(
+                        state,
+                        &CalledFromGrainUtilization(true, true),
+                    ));
                 }
                 ret.push((Self::EndTurn, DEFAULT_WEIGHT));
                 ret
             }
-            Self::UseHouseRedevelopment => {
-                ret.push((
-                    Self::Renovate(
-                        CalledFromHouseRedevelopment(true),
-                        CalledFromFarmRedevelopment(false),
-                    ),
-                    DEFAULT_WEIGHT,
-                ));
-                ret
-            }
-            Self::Renovate(from_house_redev, from_farm_redev) => {
+            Self::Renovate(from_house_redev, from_farm_redev, _) => {
                 Self::renovate_choices(state, from_house_redev, from_farm_redev)
             }
             // TODO add minor build
-            Self::UseWishForChildren => vec![(Self::GrowFamily(WithRoom(true)), DEFAULT_WEIGHT)],
-            Self::UseUrgentWishForChildren => {
-                vec![(Self::GrowFamily(WithRoom(false)), DEFAULT_WEIGHT)]
-            }
-            Self::UseCultivation => {
-                // using baked_bread = true, but this is irrelevant
-                ret.extend(Self::sow_choices(
-                    state,
-                    &CalledFromGrainUtilization(false, true),
-                ));
-                let opt_field_idx = state.next_field_position();
-
-                if let Some(field_idx) = opt_field_idx {
-                    ret.push((
-                        Self::Plow(CalledFromCultivation(true), field_idx),
-                        DEFAULT_WEIGHT,
-                    ));
-                } else {
-                    ret.push((Self::EndTurn, DEFAULT_WEIGHT));
-                }
-                ret
-            }
             Self::Plow(from_cultivation, _) => {
                 if from_cultivation.0 {
                     // using baked_bread = true, but this is irrelevant
@@ -220,16 +191,6 @@ impl Action {
                 ret.push((Self::EndTurn, DEFAULT_WEIGHT));
                 ret
             }
-            Self::UseFarmRedevelopment => {
-                ret.push((
-                    Self::Renovate(
-                        CalledFromHouseRedevelopment(false),
-                        CalledFromFarmRedevelopment(true),
-                    ),
-                    DEFAULT_WEIGHT,
-                ));
-                ret
-            }
             Self::EndTurn => Self::end_turn_choices(state),
             Self::Harvest => {
                 if !state.harvest_paid() {
@@ -242,28 +203,113 @@ impl Action {
                 ret
             }
             Self::PreHarvest => Self::harvest_choices(state),
-            Self::Convert(_, _, conversion_stage) => {
-                match conversion_stage {
-                    ConversionStage::Harvest => {
-                        ret.extend(Self::harvest_choices(state));
-                    }
-                    ConversionStage::BeforePlayOccupation(cheaper) => {
-                        ret.extend(Self::occupation_choices(state, *cheaper));
-                    }
+            Self::Convert(_, ConversionStage::BeforePlayOccupation(cheaper)) => {
+                Self::occupation_choices(state, *cheaper)
+            }
+            Self::PayFoodOrBeg(_) => vec![(Self::Harvest, DEFAULT_WEIGHT)],
+            _ => vec![(Self::EndTurn, DEFAULT_WEIGHT)],
+        }
+    }
+
+    /// The move among `next_choices` that this move stands in for. Sowing other interchangeable fields, or baking
+    /// the same grain on other improvements than the best allocation, is accepted as the offered choice.
+    #[must_use]
+    pub fn equivalent_choice(&self, state: &State) -> Self {
+        match self {
+            Self::Sow(from_grain_util, plan) => state.distinct_sowing_plan(plan).map_or_else(
+                || self.clone(),
+                |plan| Self::Sow(from_grain_util.clone(), plan),
+            ),
+            Self::BakeBread(from_grain_util, allocation) => {
+                state.best_baking_allocation(allocation).map_or_else(
+                    || self.clone(),
+                    |allocation| Self::BakeBread(from_grain_util.clone(), allocation),
+                )
+            }
+            _ => self.clone(),
+        }
+    }
+
+    fn follow_up_choices(state: &State, follow_up: FollowUp) -> Vec<WeightedAction> {
+        let mut ret: Vec<WeightedAction> = Vec::new();
+        match follow_up {
+            FollowUp::None | FollowUp::StartingPlayer => ret.push((Self::EndTurn, DEFAULT_WEIGHT)),
+            FollowUp::DayLaborer => ret.extend(Self::day_laborer_choices(state)),
+            FollowUp::Plow => {
+                if let Some(field_idx) = state.next_field_position() {
+                    ret.push((
+                        Self::Plow(CalledFromCultivation(false), field_idx),
+                        DEFAULT_WEIGHT,
+                    ));
                 }
-                ret
             }
-            Self::PayFoodOrBeg => vec![(Self::Harvest, DEFAULT_WEIGHT)],
-            Self::UseDayLaborer => {
-                ret.extend(Self::day_laborer_choices(state));
-                ret
+            FollowUp::FarmExpansion => ret.extend(Self::farm_expansion_choices(state)),
+            FollowUp::Lessons { cheaper } => ret.extend(Self::occupation_choices(state, cheaper)),
+            FollowUp::GrainUtilization => ret.extend(Self::grain_utilization_choices(state)),
+            FollowUp::Fencing => ret.extend(Self::fencing_choices(state)),
+            FollowUp::Improvements => ret.push((Self::BuildMajor, DEFAULT_WEIGHT)), // TODO : Add BuildMinor here
+            FollowUp::GrowFamily { with_room } => {
+                ret.push((Self::GrowFamily(WithRoom(with_room)), DEFAULT_WEIGHT));
+            }
+            FollowUp::HouseRedevelopment | FollowUp::FarmRedevelopment => {
+                let from_house_redev = follow_up == FollowUp::HouseRedevelopment;
+                for renovation in state.renovation_options() {
+                    ret.push((
+                        Self::Renovate(
+                            CalledFromHouseRedevelopment(from_house_redev),
+                            CalledFromFarmRedevelopment(!from_house_redev),
+                            renovation,
+                        ),
+                        DEFAULT_WEIGHT,
+                    ));
+                }
+            }
+            FollowUp::Cultivation => {
+                // using baked_bread = true, but this is irrelevant
+                ret.extend(Self::sow_choices(
+                    state,
+                    &CalledFromGrainUtilization(false, true),
+                ));
+                if let Some(field_idx) = state.next_field_position() {
+                    ret.push((
+                        Self::Plow(CalledFromCultivation(true), field_idx),
+                        DEFAULT_WEIGHT,
+                    ));
+                } else {
+                    ret.push((Self::EndTurn, DEFAULT_WEIGHT));
+                }
             }
-            _ => vec![(Self::EndTurn, DEFAULT_WEIGHT)],
+        }
+        ret
+    }
+
+    // Whether a space with this follow up leaves the player something to do
+    fn follow_up_available(state: &State, follow_up: FollowUp) -> bool {
+        match follow_up {
+            FollowUp::None | FollowUp::StartingPlayer | FollowUp::DayLaborer => true,
+            FollowUp::Plow => state.next_field_position().is_some(),
+            FollowUp::FarmExpansion => {
+                state.next_room_position().is_some() || state.next_stable_position().is_some()
+            }
+            FollowUp::Lessons { .. } => !state.occupations_available().is_empty(),
+            FollowUp::GrainUtilization => {
+                state.can_sow() || state.can_bake_bread(state.current_player_idx)
+            }
+            FollowUp::Fencing => state.can_fence(),
+            FollowUp::Improvements => state.available_majors_to_build().iter().any(|x| *x),
+            FollowUp::GrowFamily { with_room: true } => {
+                state.can_grow_family_with_room(state.current_player_idx)
+            }
+            FollowUp::GrowFamily { with_room: false } => {
+                state.can_grow_family_without_room(state.current_player_idx)
+            }
+            FollowUp::HouseRedevelopment | FollowUp::FarmRedevelopment => state.can_renovate(),
+            FollowUp::Cultivation => state.can_sow() || state.next_field_position().is_some(),
         }
     }
 
     fn occupation_choices(state: &State, cheaper: bool) -> Vec<WeightedAction> {
-        let mut required_food = if cheaper { 1 } else { 2 };
+        let mut required_food = if cheaper { 1 } else { MAX_OCCUPATION_FOOD };
         // First Occ on L1 = 0 else 1. So 0, 1, 1, 1, ..
         // First two Occs on L2 = 1 else 2. So 1, 1, 2, 2, 2, ..
         if state.num_occupations_played() == 0 && cheaper {
@@ -313,7 +359,8 @@ impl Action {
         } else if state.can_init_new_round() {
             ret.push((Self::StartRound, DEFAULT_WEIGHT));
         } else {
-            panic!("EndTurn should not result in EndGame directly");
+            // Only reachable when the ruleset has no harvest after the last round
+            ret.push((Self::EndGame, DEFAULT_WEIGHT));
         }
         ret
     }
@@ -329,9 +376,11 @@ impl Action {
             ret.push((Self::BuildMajor, DEFAULT_WEIGHT));
         }
         if from_farm_redev.0 && state.can_fence() {
+            // Fencing choices already end with ending the turn
             ret.extend(Self::fencing_choices(state));
+        } else {
+            ret.push((Self::EndTurn, DEFAULT_WEIGHT));
         }
-        ret.push((Self::EndTurn, DEFAULT_WEIGHT));
         ret
     }
 
@@ -349,7 +398,6 @@ impl Action {
                         num_from: 1,
                         num_to: 1,
                     },
-                    None,
                     conversion_stage.clone(),
                 ),
                 DEFAULT_WEIGHT,
@@ -365,7 +413,6 @@ impl Action {
                         num_from: 1,
                         num_to: 1,
                     },
-                    None,
                     conversion_stage.clone(),
                 ),
                 DEFAULT_WEIGHT,
@@ -377,7 +424,7 @@ impl Action {
                 for exchange in anytime_exchanges(idx) {
                     if state.can_use_exchange(&exchange) {
                         ret.push((
-                            Self::Convert(exchange, None, conversion_stage.clone()),
+                            Self::Convert(exchange, conversion_stage.clone()),
                             DEFAULT_WEIGHT,
                         ));
                     }
@@ -389,59 +436,11 @@ impl Action {
     }
 
     fn harvest_choices(state: &State) -> Vec<WeightedAction> {
-        let mut ret: Vec<WeightedAction> = Vec::new();
-        ret.extend(Self::anytime_conversions(state, &ConversionStage::Harvest));
-
-        for (idx, owned) in state.current_player_cards().iter().enumerate() {
-            if *owned {
-                for exchange in harvest_exchanges(idx) {
-                    if state.can_use_exchange(&exchange) {
-                        if idx == Joinery.index()
-                            && !state.current_player_flags()[UsedJoinery.index()]
-                        {
-                            ret.push((
-                                Self::Convert(
-                                    exchange,
-                                    Some(UsedJoinery.index()),
-                                    ConversionStage::Harvest,
-                                ),
-                                DEFAULT_WEIGHT,
-                            ));
-                        } else if idx == Pottery.index()
-                            && !state.current_player_flags()[UsedPottery.index()]
-                        {
-                            ret.push((
-                                Self::Convert(
-                                    exchange,
-                                    Some(UsedPottery.index()),
-                                    ConversionStage::Harvest,
-                                ),
-                                DEFAULT_WEIGHT,
-                            ));
-                        } else if idx == BasketmakersWorkshop.index()
-                            && !state.current_player_flags()[UsedBasketmakersWorkshop.index()]
-                        {
-                            ret.push((
-                                Self::Convert(
-                                    exchange,
-                                    Some(UsedBasketmakersWorkshop.index()),
-                                    ConversionStage::Harvest,
-                                ),
-                                DEFAULT_WEIGHT,
-                            ));
-                        }
-                    }
-                }
-            }
-        }
-
-        // Option to beg is only present when there are really no conversions possible
-        // Otherwise this leads to a bad average fitness from random sampling early on
-        if ret.is_empty() || state.got_enough_food() {
-            ret.push((Self::PayFoodOrBeg, DEFAULT_WEIGHT));
-        }
-
-        ret
+        state
+            .feeding_plans()
+            .into_iter()
+            .map(|plan| (Self::PayFoodOrBeg(plan), DEFAULT_WEIGHT))
+            .collect()
     }
 
     fn farm_expansion_choices(state: &State) -> Vec<WeightedAction> {
@@ -475,33 +474,30 @@ impl Action {
         from_grain_util: &CalledFromGrainUtilization,
     ) -> Vec<WeightedAction> {
         let mut ret: Vec<WeightedAction> = Vec::new();
-        if state.can_sow() && state.current_player_quantities()[Grain.index()] > 0 {
-            ret.push((
-                Self::Sow(from_grain_util.clone(), Seed::Grain),
-                DEFAULT_WEIGHT,
-            ));
-        }
-        if state.can_sow() && state.current_player_quantities()[Vegetable.index()] > 0 {
-            ret.push((
-                Self::Sow(from_grain_util.clone(), Seed::Vegetable),
-                DEFAULT_WEIGHT,
-            ));
+        if state.can_sow() {
+            for plan in state.distinct_sowing_plans() {
+                ret.push((Self::Sow(from_grain_util.clone(), plan), DEFAULT_WEIGHT));
+            }
         }
         ret
     }
 
-    fn baking_choices(state: &State, from_grain_util: bool) -> Vec<WeightedAction> {
+    // Only the best allocation for each amount of grain is offered, the others bake the same grain for less food
+    fn baking_choices(
+        state: &State,
+        from_grain_util: &CalledFromGrainUtilization,
+    ) -> Vec<WeightedAction> {
         let mut ret: Vec<WeightedAction> = Vec::new();
-        if BAKING_IMPROVEMENTS_INDICES
-            .iter()
-            .any(|&x| state.current_player_cards()[x])
-        {
-            for grain in 1..=state.current_player_quantities()[Grain.index()] {
+        if !state.can_bake_bread(state.current_player_idx) {
+            return ret;
+        }
+
+        let cards = state.current_player_cards();
+        let num_grain = state.current_player_quantities()[Grain.index()].min(MAX_BAKED_GRAIN);
+        for grain in 1..=num_grain {
+            if let Some(allocation) = best_baking_allocation(cards, grain) {
                 ret.push((
-                    Self::BakeBread(
-                        CalledFromGrainUtilization(from_grain_util, false),
-                        NumGrainToBake(grain),
-                    ),
+                    Self::BakeBread(from_grain_util.clone(), allocation),
                     DEFAULT_WEIGHT,
                 ));
             }
@@ -510,15 +506,11 @@ impl Action {
         ret
     }
 
-    fn grain_utilization_choices(state: &State, baked_already: bool) -> Vec<WeightedAction> {
+    fn grain_utilization_choices(state: &State) -> Vec<WeightedAction> {
+        let from_grain_util = CalledFromGrainUtilization(true, false);
         let mut ret: Vec<WeightedAction> = Vec::new();
-        ret.extend(Self::sow_choices(
-            state,
-            &CalledFromGrainUtilization(true, baked_already),
-        ));
-        if !baked_already {
-            ret.extend(Self::baking_choices(state, true));
-        }
+        ret.extend(Self::sow_choices(state, &from_grain_util));
+        ret.extend(Self::baking_choices(state, &from_grain_util));
         ret
     }
 
@@ -548,183 +540,68 @@ impl Action {
 
         // Weight whatever actions are available
         let mut weights = HashMap::new();
+        let available: Vec<usize> = state
+            .revealed_action_spaces()
+            .iter()
+            .copied()
+            .filter(|idx| state.occupied[*idx].is_none())
+            .collect();
 
         // Occs
-        if state.num_occupations_played() == 0 && state.occupied[Lessons1.index()].is_none() {
-            weights.insert(Lessons2.index(), ZERO_WEIGHT);
-        }
-
-        // Wood
-        let copse_wood = if state.occupied[Copse.index()].is_some() {
-            0
-        } else {
-            state.accumulated_resources[Copse.index()][Wood.index()]
-        };
-        let grove_wood = if state.occupied[Grove.index()].is_some() {
-            0
-        } else {
-            state.accumulated_resources[Grove.index()][Wood.index()]
-        };
-        let forest_wood = if state.occupied[Forest.index()].is_some() {
-            0
-        } else {
-            state.accumulated_resources[Forest.index()][Wood.index()]
-        };
-
-        if copse_wood < grove_wood || copse_wood < forest_wood {
-            weights.insert(Copse.index(), ZERO_WEIGHT);
-        }
-
-        if grove_wood < copse_wood || grove_wood < forest_wood {
-            weights.insert(Grove.index(), ZERO_WEIGHT);
-        }
-
-        if forest_wood < copse_wood || forest_wood < grove_wood {
-            weights.insert(Forest.index(), ZERO_WEIGHT);
-        }
-
-        // Clay
-        let hollow_clay = if state.occupied[Hollow.index()].is_some() {
-            0
-        } else {
-            state.accumulated_resources[Hollow.index()][Clay.index()]
-        };
-        let clay_pit_clay = if state.occupied[ClayPit.index()].is_some() {
-            0
-        } else {
-            state.accumulated_resources[ClayPit.index()][Clay.index()]
-        };
-
-        if hollow_clay < clay_pit_clay {
-            weights.insert(Hollow.index(), ZERO_WEIGHT);
-        }
-
-        if clay_pit_clay < hollow_clay {
-            weights.insert(ClayPit.index(), ZERO_WEIGHT);
-        }
-
-        // Reed
-        let reed_bank_reed = if state.occupied[ReedBank.index()].is_some() {
-            0
-        } else {
-            state.accumulated_resources[ReedBank.index()][Reed.index()]
-        };
-        if state.occupied[ResourceMarket.index()].is_none() && reed_bank_reed == 1 {
-            weights.insert(ReedBank.index(), ZERO_WEIGHT);
-        }
-
-        // Food
-        let traveling_players_food = if state.occupied[TravelingPlayers.index()].is_some() {
-            0
-        } else {
-            state.accumulated_resources[TravelingPlayers.index()][Food.index()]
-        };
-        let fishing_food = if state.occupied[Fishing.index()].is_some() {
-            0
-        } else {
-            state.accumulated_resources[Fishing.index()][Food.index()]
-        };
-        let day_laborer_food = if state.occupied[DayLaborer.index()].is_some() {
-            0
-        } else {
-            2
-        };
-
-        if traveling_players_food < fishing_food || traveling_players_food < day_laborer_food {
-            weights.insert(TravelingPlayers.index(), ZERO_WEIGHT);
-        }
-
-        if fishing_food < traveling_players_food || fishing_food < day_laborer_food {
-            weights.insert(Fishing.index(), ZERO_WEIGHT);
-        }
-
-        if (day_laborer_food < traveling_players_food || day_laborer_food < fishing_food)
-            && !state.current_player_cards()[AssistantTiller.index()]
-        {
-            weights.insert(DayLaborer.index(), ZERO_WEIGHT);
-        }
-
-        for i in 0..OPEN_SPACES + state.current_round {
-            let idx = state.action_spaces[i];
-            if state.occupied[idx].is_some() {
-                continue;
-            }
-
-            if idx == Farmland.index() && state.next_field_position().is_none() {
-                continue;
-            }
-            if idx == FarmExpansion.index()
-                && state.next_room_position().is_none()
-                && state.next_stable_position().is_none()
-            {
-                continue;
-            }
-
-            if idx == Improvements.index() && !state.available_majors_to_build().iter().any(|x| *x)
-            {
-                continue;
-            }
-
-            if idx == Fencing.index() && !state.can_fence() {
-                continue;
-            }
-
-            if idx == GrainUtilization.index()
-                && !state.can_sow()
-                && !state.can_bake_bread(state.current_player_idx)
-            {
-                continue;
-            }
-
-            if idx == HouseRedevelopment.index() && !state.can_renovate() {
-                continue;
-            }
-
-            if idx == FarmRedevelopment.index() && !state.can_renovate() {
-                continue;
-            }
-
-            if idx == WishForChildren.index()
-                && !state.can_grow_family_with_room(state.current_player_idx)
-            {
-                continue;
-            }
-
-            if idx == UrgentWishForChildren.index()
-                && !state.can_grow_family_without_room(state.current_player_idx)
-            {
-                continue;
+        let cheaper_lessons_free = available.iter().any(|idx| {
+            matches!(
+                state.action_space(*idx).follow_up,
+                FollowUp::Lessons { cheaper: true }
+            )
+        });
+        if state.num_occupations_played() == 0 && cheaper_lessons_free {
+            for idx in &available {
+                if matches!(
+                    state.action_space(*idx).follow_up,
+                    FollowUp::Lessons { cheaper: false }
+                ) {
+                    weights.insert(*idx, ZERO_WEIGHT);
+                }
             }
+        }
 
-            if idx == Cultivation.index()
-                && !state.can_sow()
-                && state.next_field_position().is_none()
+        // Spaces that only give goods, when another free one gives as much of each and more of something
+        let goods_only: Vec<(usize, Resources)> = available
+            .iter()
+            .filter(|idx| {
+                matches!(
+                    state.action_space(**idx).follow_up,
+                    FollowUp::None | FollowUp::DayLaborer
+                )
+            })
+            .map(|idx| (*idx, Self::space_goods(state, *idx)))
+            .collect();
+        for (idx, goods) in &goods_only {
+            // The Assistant Tiller makes the Day Laborer worth more than its food
+            if state.action_space(*idx).follow_up == FollowUp::DayLaborer
+                && state.current_player_cards()[AssistantTiller.index()]
             {
                 continue;
             }
-
-            if idx == Lessons1.index() && state.occupations_available().is_empty() {
-                continue;
+            let dominated = goods_only.iter().any(|(_, other)| {
+                other.iter().zip(goods).all(|(a, b)| a >= b)
+                    && other.iter().zip(goods).any(|(a, b)| a > b)
+            });
+            if dominated {
+                weights.insert(*idx, ZERO_WEIGHT);
             }
+        }
 
-            if idx == Lessons2.index() && state.occupations_available().is_empty() {
+        for idx in available {
+            if !Self::follow_up_available(state, state.action_space(idx).follow_up) {
                 continue;
             }
 
             // Growing before others
-            if idx == WishForChildren.index()
-                && state.occupied[WishForChildren.index()].is_none()
-                && state.can_grow_family_with_room(state.current_player_idx)
-                && (0..state.num_players)
-                    .filter(|&x| x != state.current_player_idx)
-                    .any(|x| state.can_grow_family_with_room(x))
-            {
-                weights.insert(idx, LARGE_WEIGHT);
-            }
-
-            if idx == UrgentWishForChildren.index()
-                && state.occupied[UrgentWishForChildren.index()].is_none()
-                && state.can_grow_family_with_room(state.current_player_idx)
+            if matches!(
+                state.action_space(idx).follow_up,
+                FollowUp::GrowFamily { .. }
+            ) && state.can_grow_family_with_room(state.current_player_idx)
                 && (0..state.num_players)
                     .filter(|&x| x != state.current_player_idx)
                     .any(|x| state.can_grow_family_with_room(x))
@@ -737,11 +614,17 @@ impl Action {
             } else {
                 DEFAULT_WEIGHT
             };
-            ret.push((Self::action_space_idx_to_action(idx), w));
+            ret.push((Self::UseSpace(idx), w));
         }
         ret
     }
 
+    // Goods a player gets from an action space now
+    fn space_goods(state: &State, idx: usize) -> Resources {
+        let space = state.action_space(idx);
+        core::array::from_fn(|i| state.accumulated_resources[idx][i] + space.fixed[i])
+    }
+
     fn build_major_choices(state: &State) -> Vec<WeightedAction> {
         let mut ret: Vec<WeightedAction> = Vec::new();
 
@@ -771,104 +654,59 @@ impl Action {
         ret
     }
 
-    pub fn display(&self) {
-        println!("\nChosen Action : {self:?}");
-    }
-
-    fn action_space_idx_to_action(idx: usize) -> Action {
-        match idx {
-            0 => Self::UseCopse,
-            1 => Self::UseGrove,
-            2 => Self::UseForest,
-            3 => Self::UseResourceMarket,
-            4 => Self::UseHollow,
-            5 => Self::UseClayPit,
-            6 => Self::UseReedBank,
-            7 => Self::UseTravelingPlayers,
-            8 => Self::UseFishing,
-            9 => Self::UseDayLaborer,
-            10 => Self::UseGrainSeeds,
-            11 => Self::UseMeetingPlace,
-            12 => Self::UseFarmland,
-            13 => Self::UseFarmExpansion,
-            14 => Self::UseLessons(true),
-            15 => Self::UseLessons(false),
-            16 => Self::UseSheepMarket,
-            17 => Self::UseGrainUtilization,
-            18 => Self::UseFencing,
-            19 => Self::UseImprovements,
-            20 => Self::UseWishForChildren,
-            21 => Self::UseWesternQuarry,
-            22 => Self::UseHouseRedevelopment,
-            23 => Self::UsePigMarket,
-            24 => Self::UseVegetableSeeds,
-            25 => Self::UseEasternQuarry,
-            26 => Self::UseCattleMarket,
-            27 => Self::UseCultivation,
-            28 => Self::UseUrgentWishForChildren,
-            29 => Self::UseFarmRedevelopment,
-            _ => panic!("Invalid action space index"),
-        }
+    pub fn display(&self, state: &State) {
+        println!("\nChosen Action : {}", self.describe(state));
     }
 
+    /// Like the debug output, with action spaces named as in the ruleset of `state`
     #[must_use]
-    pub fn action_idx(&self) -> usize {
+    pub fn describe(&self, state: &State) -> String {
         match self {
-            Self::UseCopse => 0,
-            Self::UseGrove => 1,
-            Self::UseForest => 2,
-            Self::UseResourceMarket => 3,
-            Self::UseHollow => 4,
-            Self::UseClayPit => 5,
-            Self::UseReedBank => 6,
-            Self::UseTravelingPlayers => 7,
-            Self::UseFishing => 8,
-            Self::UseDayLaborer => 9,
-            Self::UseGrainSeeds => 10,
-            Self::UseMeetingPlace => 11,
-            Self::UseFarmland => 12,
-            Self::UseFarmExpansion => 13,
-            Self::UseLessons(true) => 14,
-            Self::UseLessons(false) => 15,
-            Self::UseSheepMarket => 16,
-            Self::UseGrainUtilization => 17,
-            Self::UseFencing => 18,
-            Self::UseImprovements => 19,
-            Self::UseWishForChildren => 20,
-            Self::UseWesternQuarry => 21,
-            Self::UseHouseRedevelopment => 22,
-            Self::UsePigMarket => 23,
-            Self::UseVegetableSeeds => 24,
-            Self::UseEasternQuarry => 25,
-            Self::UseCattleMarket => 26,
-            Self::UseCultivation => 27,
-            Self::UseUrgentWishForChildren => 28,
-            Self::UseFarmRedevelopment => 29,
-            Self::StartRound => 30,
-            Self::PlaceWorker => 31,
-            Self::BuildRoom(_) => 32,
-            Self::BuildStable(_) => 33,
-            Self::BuildCard(_, _) => 34,
-            Self::Harvest => 35,
-            Self::EndTurn => 36,
-            Self::EndGame => 37,
-            Self::BuildMajor => 38,
-            Self::BakeBread(_, _) => 39,
-            Self::Sow(_, _) => 40,
-            Self::Renovate(_, _) => 41,
-            Self::GrowFamily(_) => 42,
-            Self::Fence(_) => 43,
-            Self::Plow(_, _) => 44,
-            Self::Convert(_, _, _) => 45,
-            Self::PreHarvest => 46,
-            Self::PayFoodOrBeg => 47,
-            Self::StartGame => 48,
-            Self::PlayOccupation(_, _) => 49,
-            Self::GetResourceFromChildless(_) => 50,
+            Self::UseSpace(idx) => {
+                let space = state.action_space(*idx);
+                let goods: Resources =
+                    core::array::from_fn(|i| space.accumulation[i] + space.fixed[i]);
+                if goods.iter().any(|x| *x > 0) {
+                    format!("{} ({})", space.name, format_resources(&goods).trim_start())
+                } else {
+                    space.name.clone()
+                }
+            }
+            Self::RevealRoundCard(idx) => format!("Reveal {}", state.action_space(*idx).name),
+            _ => format!("{self:?}"),
         }
     }
 
+    /// Applies one of `next_choices`, moves from elsewhere should go through `State::try_apply`
+    /// # Panics
+    /// If the move breaks a rule, which a move from `next_choices` never does
     pub fn apply_choice(&self, state: &mut State) {
+        if let Err(err) = self.apply(state) {
+            panic!("{self:?} cannot be applied : {err}");
+        }
+    }
+
+    /// Carries out the move. The rules the underlying operations check are reported as errors,
+    /// the state may be left partly changed then.
+    /// # Errors
+    /// If the move breaks one of those rules
+    pub fn apply(&self, state: &mut State) -> Result<(), RuleError> {
+        match self {
+            Self::UseSpace(idx) if !state.revealed_action_spaces().contains(idx) => {
+                return Err(RuleError::IllegalAction(self.clone()));
+            }
+            Self::UseSpace(idx) if state.occupied[*idx].is_some() => {
+                return Err(RuleError::ActionSpaceOccupied(*idx));
+            }
+            Self::BuildCard(idx, _) if !MAJOR_IMPROVEMENTS_INDICES.contains(idx) => {
+                return Err(RuleError::IllegalAction(self.clone()));
+            }
+            Self::PlayOccupation(idx, _) if !OCCUPATIONS_INDICES.contains(idx) => {
+                return Err(RuleError::IllegalAction(self.clone()));
+            }
+            _ => (),
+        }
+
         state.add_action(self);
         match self {
             Self::GetResourceFromChildless(res) => {
@@ -877,125 +715,105 @@ impl Action {
                 state.current_player_quantities_mut()[Food.index()] += 1;
                 state.current_player_flags_mut()[BeforeRoundStart.index()] = false;
             }
-            Self::StartRound => {
-                state.init_new_round();
-            }
-            Self::UseMeetingPlace => {
-                state.starting_player_idx = state.current_player_idx;
+            Self::RevealRoundCard(card_idx) => {
+                state.init_new_round(*card_idx);
             }
+            Self::UseSpace(idx) => state.use_action_space(*idx),
             Self::PlayOccupation(occ, food_cost) => {
-                //state.player_mut().occupations.push(occ.clone());
+                let mut cost = new_res();
+                cost[Food.index()] = *food_cost;
+                pay_for_resource(&cost, state.current_player_quantities_mut())?;
                 state.current_player_cards_mut()[*occ] = true;
-                state.current_player_quantities_mut()[Food.index()] -= food_cost;
             }
             Self::Plow(_, pasture_idx) => {
-                state.add_new_field(pasture_idx);
+                state.add_new_field(pasture_idx)?;
             }
             Self::Fence(pasture_config) => {
                 state.fence(pasture_config);
             }
             Self::BuildRoom(pasture_idx) => {
-                state.build_room(pasture_idx);
+                state.build_room(pasture_idx)?;
             }
             Self::BuildStable(pasture_idx) => {
-                state.build_stable(pasture_idx);
+                state.build_stable(pasture_idx)?;
             }
-            Self::Sow(_called_from_grain_util, seed) => {
-                state.sow_field(seed);
+            Self::Sow(_called_from_grain_util, plan) => {
+                state.sow(plan)?;
             }
             Self::BuildCard(major_idx, return_fireplace) => {
-                state.build_major(*major_idx, return_fireplace.0);
+                state.build_major(*major_idx, return_fireplace.0)?;
             }
-            Self::BakeBread(_called_from_grain_util, num_grain_to_bake) => {
-                state.bake_bread(num_grain_to_bake.0);
+            Self::BakeBread(_called_from_grain_util, allocation) => {
+                state.bake_bread(allocation);
             }
-            Self::Renovate(_from_house_redev, _from_farm_redev) => {
-                state.renovate();
+            Self::Renovate(_from_house_redev, _from_farm_redev, renovation) => {
+                state.renovate(renovation)?;
             }
             Self::GrowFamily(with_room) => state.grow_family(with_room.0),
             Self::EndTurn => state.end_turn(),
             Self::PreHarvest => state.harvest_fields(),
-            Self::Convert(res_ex, opt_major_used, _) => {
-                state.use_exchange(res_ex);
-                if let Some(used_major_idx) = opt_major_used {
-                    state.current_player_flags_mut()[*used_major_idx] = true;
-                }
-            }
-            Self::PayFoodOrBeg => state.pay_food_or_beg(),
+            Self::Convert(res_ex, _) => state.use_exchange(res_ex),
+            Self::PayFoodOrBeg(plan) => state.pay_food_or_beg(plan),
             _ => (),
         }
 
-        // Collect resources if possible
-        if ACCUMULATION_SPACE_INDICES.contains(&self.action_idx()) {
-            let res = state.accumulated_resources[self.action_idx()];
-            take_resources(state.current_player_quantities_mut(), &res);
-
-            if self.action_idx() == SheepMarket.index()
-                || self.action_idx() == PigMarket.index()
-                || self.action_idx() == CattleMarket.index()
-            {
-                state.accommodate_animals(false);
-            }
-
-            state.accumulated_resources[self.action_idx()] = new_res();
-        }
-
-        if RESOURCE_SPACE_INDICES.contains(&self.action_idx()) {
-            get_resource(self.action_idx(), state.current_player_quantities_mut());
+        if state.debug_validate {
+            let violations = state.validate();
+            assert!(
+                violations.is_empty(),
+                "Invalid state after {self:?} : {}",
+                violations
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
         }
+        Ok(())
     }
 }
 
 impl Debug for Action {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::UseCopse => write!(f, "Copse ({})", RESOURCE_EMOJIS[Wood.index()]),
-            Self::UseGrove => write!(f, "Grove ({})", RESOURCE_EMOJIS[Wood.index()].repeat(2)),
-            Self::UseForest => write!(f, "Forest ({})", RESOURCE_EMOJIS[Wood.index()].repeat(3)),
-            Self::UseResourceMarket => write!(
-                f,
-                "Resource Market ({} {} {})",
-                RESOURCE_EMOJIS[Food.index()],
-                RESOURCE_EMOJIS[Stone.index()],
-                RESOURCE_EMOJIS[Reed.index()]
-            ),
-            Self::UseHollow => write!(f, "Hollow ({})", RESOURCE_EMOJIS[Clay.index()].repeat(2)),
-            Self::UseClayPit => write!(f, "Clay Pit ({})", RESOURCE_EMOJIS[Clay.index()]),
-            Self::UseReedBank => write!(f, "Reed Bank ({})", RESOURCE_EMOJIS[Reed.index()]),
-            Self::UseTravelingPlayers => {
-                write!(f, "Traveling Players ({})", RESOURCE_EMOJIS[Food.index()])
-            }
-            Self::UseFishing => write!(f, "Fishing {}", RESOURCE_EMOJIS[Food.index()]),
-            Self::UseDayLaborer => write!(
-                f,
-                "Day Laborer ({})",
-                RESOURCE_EMOJIS[Food.index()].repeat(2)
-            ),
-            Self::UseGrainSeeds => write!(f, "Grain Seeds ({})", RESOURCE_EMOJIS[Grain.index()]),
-            Self::UseMeetingPlace => write!(f, "Meeting Place"),
-            Self::UseFarmland => write!(f, "Farmland"),
-            Self::UseFarmExpansion => write!(f, "Farm Expansion"),
-            Self::UseLessons(cheaper) => write!(f, "Lessons ({})", if *cheaper { 1 } else { 2 }),
-            Self::UseGrainUtilization => write!(f, "Grain Utilization"),
-            Self::UseFencing => write!(f, "Fencing"),
-            Self::UseImprovements => write!(f, "Improvements"),
-            Self::UseWishForChildren => write!(f, "Wish For Children"),
-            Self::UseHouseRedevelopment => write!(f, "House Redevelopment"),
-            Self::UseCultivation => write!(f, "Cultivation"),
-            Self::UseUrgentWishForChildren => write!(f, "Urgent Wish For Children"),
-            Self::UseFarmRedevelopment => write!(f, "Farm Redevelopment"),
+            Self::UseSpace(idx) => write!(f, "Use Space ({idx})"),
             Self::StartRound => write!(f, "Start Round"),
+            Self::RevealRoundCard(idx) => write!(f, "Reveal Round Card ({idx})"),
             Self::PlaceWorker => write!(f, "Place Worker"),
             Self::BuildRoom(idx) => write!(f, "Build Room ({idx})"),
             Self::BuildStable(idx) => write!(f, "Build Stable ({idx})"),
-            Self::BuildCard(idx, _) => write!(f, "Build Card ({})", CARD_NAMES[*idx]),
+            Self::BuildCard(idx, _) => match CARD_NAMES.get(*idx) {
+                Some(name) => write!(f, "Build Card ({name})"),
+                None => write!(f, "Build Card ({idx})"),
+            },
             Self::Harvest => write!(f, "Harvest"),
             Self::EndTurn => write!(f, "End Turn"),
             Self::EndGame => write!(f, "End Game"),
             Self::BuildMajor => write!(f, "Build Major"),
-            Self::BakeBread(_, num) => write!(f, "Bake Bread from {} Grain", num.0),
-            Self::Sow(_, seed) => write!(f, "Sow ({seed:?})"),
-            Self::Renovate(_, _) => write!(f, "Renovate"),
+            Self::BakeBread(_, allocation) => {
+                write!(
+                    f,
+                    "Bake Bread ({} {} to {} {})",
+                    allocation_grain(allocation),
+                    RESOURCE_EMOJIS[Grain.index()],
+                    allocation_food(allocation),
+                    RESOURCE_EMOJIS[Food.index()]
+                )?;
+                for (major_idx, grain) in allocation {
+                    match CARD_NAMES.get(*major_idx) {
+                        Some(name) => write!(f, " [{name} x{grain}]")?,
+                        None => write!(f, " [{major_idx} x{grain}]")?,
+                    }
+                }
+                Ok(())
+            }
+            Self::Sow(_, plan) => write!(f, "Sow {plan:?}"),
+            Self::Renovate(_, _, renovation) => write!(
+                f,
+                "Renovate ({}) for{}",
+                RESOURCE_EMOJIS[renovation.target],
+                format_resources(&renovation.cost)
+            ),
             Self::GrowFamily(_) => write!(f, "Grow Family"),
             Self::Fence(pasture_config) => write!(
                 f,
@@ -1003,7 +821,7 @@ impl Debug for Action {
                 pasture_config.pastures, pasture_config.wood
             ),
             Self::Plow(_, pasture_idx) => write!(f, "Plow ({pasture_idx})"),
-            Self::Convert(res_ex, _, _) => write!(
+            Self::Convert(res_ex, _) => write!(
                 f,
                 "Convert ({}{} to {}{})",
                 res_ex.num_from,
@@ -1012,31 +830,93 @@ impl Debug for Action {
                 RESOURCE_EMOJIS[res_ex.to]
             ),
             Self::PreHarvest => write!(f, "Pre Harvest"),
-            Self::PayFoodOrBeg => write!(f, "Pay Food (Or Beg)"),
+            Self::PayFoodOrBeg(plan) => {
+                write!(f, "Pay Food")?;
+                for (res_ex, times) in &plan.exchanges {
+                    write!(
+                        f,
+                        " [{}{} to {}{}]",
+                        res_ex.num_from * times,
+                        RESOURCE_EMOJIS[res_ex.from],
+                        res_ex.num_to * times,
+                        RESOURCE_EMOJIS[res_ex.to]
+                    )?;
+                }
+                if plan.begging_tokens > 0 {
+                    write!(f, " (Beg {})", plan.begging_tokens)?;
+                }
+                Ok(())
+            }
             Self::StartGame => write!(f, "Start Game"),
-            Self::PlayOccupation(occ, _) => write!(f, "Play Occupation ({})", CARD_NAMES[*occ]),
+            Self::PlayOccupation(occ, _) => match CARD_NAMES.get(*occ) {
+                Some(name) => write!(f, "Play Occupation ({name})"),
+                None => write!(f, "Play Occupation ({occ})"),
+            },
             Self::GetResourceFromChildless(res) => write!(
                 f,
                 "Childless ({} + {})",
                 RESOURCE_EMOJIS[Food.index()],
                 RESOURCE_EMOJIS[*res]
             ),
-            Self::UseSheepMarket => write!(f, "Sheep Market ({})", RESOURCE_EMOJIS[Sheep.index()]),
-            Self::UseWesternQuarry => {
-                write!(f, "Western Quarry ({})", RESOURCE_EMOJIS[Stone.index()])
-            }
-            Self::UsePigMarket => write!(f, "Pig Market ({})", RESOURCE_EMOJIS[Boar.index()]),
-            Self::UseVegetableSeeds => write!(
-                f,
-                "Vegetable Seeds ({})",
-                RESOURCE_EMOJIS[Vegetable.index()]
-            ),
-            Self::UseEasternQuarry => {
-                write!(f, "Eastern Quarry ({})", RESOURCE_EMOJIS[Stone.index()])
-            }
-            Self::UseCattleMarket => {
-                write!(f, "Cattle Market ({})", RESOURCE_EMOJIS[Cattle.index()])
-            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agricola::algorithms::PlayerType;
+    use crate::agricola::quantity::{Clay, Reed, Wood};
+
+    #[test]
+    fn test_farm_redevelopment_ends_turn_once() {
+        let mut state = State::new(&[PlayerType::Human]).unwrap();
+        state.current_player_quantities_mut()[Wood.index()] = 4;
+        state.current_player_quantities_mut()[Clay.index()] = 2;
+        state.current_player_quantities_mut()[Reed.index()] = 1;
+        let renovation = state.renovation_options().pop().unwrap();
+        state.last_action = Action::Renovate(
+            CalledFromHouseRedevelopment(false),
+            CalledFromFarmRedevelopment(true),
+            renovation,
+        );
+        assert!(state.can_fence());
+
+        let choices = Action::next_choices(&state);
+        let num_end_turn = choices
+            .iter()
+            .filter(|(action, _)| *action == Action::EndTurn)
+            .count();
+        assert_eq!(num_end_turn, 1);
+        assert!(choices.len() > 1);
+    }
+
+    #[test]
+    fn test_dominated_spaces_are_not_weighted() {
+        let mut state = State::with_seed(&[PlayerType::Human; 2], 3).unwrap();
+        while state.last_action != Action::PlaceWorker {
+            let choices = Action::next_choices(&state);
+            choices[0].0.apply_choice(&mut state);
+        }
+
+        let weight = |name: &str| {
+            Action::next_choices(&state)
+                .into_iter()
+                .find(|(action, _)| matches!(action, Action::UseSpace(idx) if state.action_space(*idx).name == name))
+                .map(|(_, weight)| weight)
+                .unwrap()
+        };
+        // One wood is worth less than three, one reed less than the Resource Market, one food less than two
+        assert!(weight("Copse") < DEFAULT_WEIGHT);
+        assert!(weight("Grove") < DEFAULT_WEIGHT);
+        assert!(weight("Forest") >= DEFAULT_WEIGHT);
+        assert!(weight("Reed Bank") < DEFAULT_WEIGHT);
+        assert!(weight("Fishing") < DEFAULT_WEIGHT);
+        assert!(weight("Day Laborer") >= DEFAULT_WEIGHT);
+        // Nothing gives more grain
+        assert!(weight("Grain Seeds") >= DEFAULT_WEIGHT);
+        // No occupation played yet
+        assert!(weight("Lessons(2)") < DEFAULT_WEIGHT);
+        assert!(weight("Lessons(1)") >= DEFAULT_WEIGHT);
+    }
+}
@@ -1,5 +1,8 @@
 use super::actions::Action;
 use super::state::{State, MAX_NUM_PLAYERS};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 const MCTS_EXPLORATION_PARAM: f64 = 2.0;
@@ -36,11 +39,13 @@ pub struct GameRecord {
     pub total_games: usize,
 }
 
-#[derive(Debug, Copy, Clone, Hash, PartialEq)]
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Serialize, Deserialize)]
 pub enum PlayerType {
     Human,
     MctsAI,
     TdAI,
+    // Moves are chosen by a child process speaking the engine protocol, see `external.rs`
+    External,
 }
 
 /// Used to store the average fitness of a node and the number of times it has been visited for all actions to be taken in the next turn
@@ -84,6 +89,20 @@ impl GameRecord {
 pub struct AI {
     pub num_games_sampled: usize,
     pub cache: HashMap<u64, GameRecord>,
+    rng: ChaCha8Rng,
+    // Hash of the state last searched and what its player to move observes of it
+    searched: Option<(u64, State)>,
+}
+
+// Seed of the hidden order of the round cards in searched states. The same every time, so the nodes of the tree keep their hashes.
+const SEARCH_SEED: u64 = 0;
+
+// The state searched for the player to move : what they observe, with the order of the round cards to come made up.
+// Searches draw the round cards from their own random number generator, so the made up order is never used.
+fn searched_state(state: &State) -> State {
+    state
+        .observation(state.current_player_idx)
+        .determinize(SEARCH_SEED)
 }
 
 impl Default for AI {
@@ -95,19 +114,30 @@ impl Default for AI {
 impl AI {
     #[must_use]
     pub fn new() -> Self {
+        Self::with_seed(rand::random())
+    }
+
+    /// An AI whose simulations are reproducible
+    #[must_use]
+    pub fn with_seed(seed: u64) -> Self {
         Self {
             num_games_sampled: 0,
             cache: HashMap::new(),
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            searched: None,
         }
     }
 
     pub fn reset(&mut self) {
         self.num_games_sampled = 0;
         self.cache.clear();
+        self.searched = None;
     }
 
+    /// The moves of the player to move in `state`, searched from what that player observes
     #[must_use]
     pub fn get_simulation_records(state: &State) -> Vec<SimulationRecord> {
+        let state = &searched_state(state);
         let mut records: Vec<SimulationRecord> = Vec::new();
         let actions = Action::next_choices(state);
         for (action, weight) in actions {
@@ -190,6 +220,10 @@ impl AI {
             let sub_choices = Action::next_choices(state);
             if sub_choices.is_empty() {
                 break;
+            } else if state.is_chance_node() {
+                // Chance outcomes are sampled, each outcome gets its own node below this one
+                let outcome = state.sample_chance();
+                outcome.apply_choice(state);
             } else if sub_choices.len() == 1 {
                 sub_choices[0].0.apply_choice(state);
             } else {
@@ -205,6 +239,8 @@ impl AI {
         path
     }
 
+    /// Simulates one more game from what the player to move in `state` observes, the seed and the order
+    /// of the round cards to come are never looked at
     /// # Panics
     /// Only works for AI player types, panics otherwise
     pub fn sample_once(
@@ -213,11 +249,18 @@ impl AI {
         state: &State,
         opt_depth: Option<usize>,
     ) {
+        let hash = state.get_hash();
+        let (_, searched) = self
+            .searched
+            .take()
+            .filter(|(h, _)| *h == hash)
+            .unwrap_or_else(|| (hash, searched_state(state)));
         match state.player_type(state.current_player_idx) {
-            PlayerType::TdAI => self.sample_once_td(records, state, opt_depth),
-            PlayerType::MctsAI => self.sample_once_mcts(records, state, opt_depth),
-            PlayerType::Human => panic!("Invalid AI player type"),
+            PlayerType::TdAI => self.sample_once_td(records, &searched, opt_depth),
+            PlayerType::MctsAI => self.sample_once_mcts(records, &searched, opt_depth),
+            PlayerType::Human | PlayerType::External => panic!("Invalid AI player type"),
         }
+        self.searched = Some((hash, searched));
     }
 
     fn sample_once_td(
@@ -228,6 +271,7 @@ impl AI {
     ) {
         let first_action = self.choose_uct(state.current_player_idx, records);
         let mut tmp_state: State = state.clone();
+        tmp_state.reseed_rng(self.rng.gen());
         first_action.apply_choice(&mut tmp_state);
 
         let path = self.trace_path_uct(&mut tmp_state);
@@ -300,6 +344,7 @@ impl AI {
     ) {
         let first_action = self.choose_uct(state.current_player_idx, records);
         let mut tmp_state: State = state.clone();
+        tmp_state.reseed_rng(self.rng.gen());
         first_action.apply_choice(&mut tmp_state);
 
         let path = self.trace_path_uct(&mut tmp_state);
@@ -1,19 +1,48 @@
+use super::farm::Seed;
 use super::quantity::{
     new_res, Boar, Cattle, Clay, Food, Grain, Quantities, Quantity, Reed, ResourceExchange,
     Resources, Sheep, Stone, Vegetable, Wood,
 };
+use serde::{Deserialize, Serialize};
 
 pub trait Card {
     fn index(&self) -> usize;
 }
 
-pub const NUM_CARDS: usize = 12;
+pub const NUM_CARDS: usize = 13;
 pub const MAJOR_IMPROVEMENTS_INDICES: [usize; 10] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
 pub const COOKING_IMPROVEMENTS_INDICES: [usize; 4] = [0, 1, 2, 3];
 pub const FIREPLACE_INDICES: [usize; 2] = [0, 1];
 pub const COOKING_HEARTH_INDICES: [usize; 2] = [2, 3];
 pub const BAKING_IMPROVEMENTS_INDICES: [usize; 6] = [0, 1, 2, 3, 5, 6];
-pub const OCCUPATIONS_INDICES: [usize; 2] = [10, 11];
+pub const OCCUPATIONS_INDICES: [usize; 3] = [10, 11, 12];
+/// Most grain baked in one baking action
+pub const MAX_BAKED_GRAIN: usize = 8;
+
+/// Grain baked on each baking improvement : (card index, grain)
+pub type BakingAllocation = Vec<(usize, usize)>;
+
+const GRAIN_SOW_AMOUNT: usize = 3;
+const VEGETABLE_SOW_AMOUNT: usize = 2;
+
+// Extra goods placed on each sown field by played cards : (card index, seed, extra goods)
+const SOW_BONUSES: [(usize, Seed, usize); 0] = [];
+
+// House renovations : (card index enabling it or None if always allowed, from material, to material)
+fn renovations() -> [(Option<usize>, usize, usize); 3] {
+    [
+        (None, Wood.index(), Clay.index()),
+        (None, Clay.index(), Stone.index()),
+        (Some(Conservator.index()), Wood.index(), Stone.index()),
+    ]
+}
+
+/// Renovating the house to the `target` material by paying `cost`
+#[derive(Clone, Debug, Hash, PartialEq, Serialize, Deserialize)]
+pub struct Renovation {
+    pub target: usize,
+    pub cost: Resources,
+}
 
 pub const CARD_NAMES: [&str; NUM_CARDS] = [
     "Major : Fireplace (2 🧱)",
@@ -28,6 +57,7 @@ pub const CARD_NAMES: [&str; NUM_CARDS] = [
     "Major : Basketmaker's Workshop",
     "Occupation : Assistant Tiller",
     "Occupation : Childless",
+    "Occupation : Conservator",
 ];
 
 /// Major improvements
@@ -45,6 +75,7 @@ pub struct BasketmakersWorkshop;
 /// Occupations
 pub struct AssistantTiller;
 pub struct Childless;
+pub struct Conservator;
 
 impl Card for Fireplace1 {
     fn index(&self) -> usize {
@@ -118,6 +149,12 @@ impl Card for Childless {
     }
 }
 
+impl Card for Conservator {
+    fn index(&self) -> usize {
+        12
+    }
+}
+
 pub trait MajorImprovement {
     fn anytime_exchanges(&self) -> Vec<ResourceExchange> {
         vec![]
@@ -128,6 +165,10 @@ pub trait MajorImprovement {
     fn harvest_exchanges(&self) -> Vec<ResourceExchange> {
         vec![]
     }
+    // Goods placed on the spaces of the following rounds when built, starting with the next round
+    fn future_goods(&self) -> Vec<Resources> {
+        vec![]
+    }
     fn points(&self, quantities: &Quantities) -> u32;
     fn cost(&self) -> Resources;
 }
@@ -333,6 +374,12 @@ impl MajorImprovement for CookingHearth2 {
 }
 
 impl MajorImprovement for Well {
+    fn future_goods(&self) -> Vec<Resources> {
+        let mut res = new_res();
+        res[Food.index()] = 1;
+        vec![res; 5]
+    }
+
     fn points(&self, _quantities: &Quantities) -> u32 {
         4
     }
@@ -521,6 +568,14 @@ pub fn harvest_exchanges(major_idx: usize) -> Vec<ResourceExchange> {
     }
 }
 
+#[must_use]
+pub fn future_goods(major_idx: usize) -> Vec<Resources> {
+    match major_idx {
+        4 => Well.future_goods(),
+        _ => vec![],
+    }
+}
+
 #[must_use]
 pub fn points(major_idx: usize, quantities: &Quantities) -> u32 {
     match major_idx {
@@ -554,3 +609,127 @@ pub fn cost(major_idx: usize) -> Resources {
         _ => new_res(),
     }
 }
+
+/// Goods placed on a field when sowing `seed`, including bonuses from the played `cards`
+#[must_use]
+pub fn sow_amount(seed: Seed, cards: &[bool]) -> usize {
+    let base = match seed {
+        Seed::Grain => GRAIN_SOW_AMOUNT,
+        Seed::Vegetable => VEGETABLE_SOW_AMOUNT,
+    };
+    let bonus: usize = SOW_BONUSES
+        .iter()
+        .filter(|(card_idx, bonus_seed, _)| cards[*card_idx] && *bonus_seed == seed)
+        .map(|(_, _, extra)| extra)
+        .sum();
+    base + bonus
+}
+
+/// Renovations allowed from a house of `material` with `num_rooms` rooms, given the played `cards`.
+/// Each renovation costs one of the target material per room and one reed.
+#[must_use]
+pub fn renovation_options(material: usize, num_rooms: usize, cards: &[bool]) -> Vec<Renovation> {
+    renovations()
+        .into_iter()
+        .filter(|(card_idx, from, _)| *from == material && card_idx.is_none_or(|idx| cards[idx]))
+        .map(|(_, _, to)| {
+            let mut cost = new_res();
+            cost[to] = num_rooms;
+            cost[Reed.index()] = 1;
+            Renovation { target: to, cost }
+        })
+        .collect()
+}
+
+/// Maximum grain the improvement can bake in one baking action, None if unlimited
+#[must_use]
+pub fn baking_capacity(major_idx: usize) -> Option<usize> {
+    match major_idx {
+        _ if major_idx == ClayOven.index() => Some(1),
+        _ if major_idx == StoneOven.index() => Some(2),
+        _ if BAKING_IMPROVEMENTS_INDICES.contains(&major_idx) => None,
+        _ => Some(0),
+    }
+}
+
+/// Food gained by baking `num_grain` grain on a single improvement
+#[must_use]
+pub fn baking_food(major_idx: usize, num_grain: usize) -> usize {
+    baking_exchanges(major_idx)
+        .first()
+        .map_or(0, |ex| num_grain * ex.num_to / ex.num_from)
+}
+
+#[must_use]
+pub fn allocation_food(allocation: &BakingAllocation) -> usize {
+    allocation
+        .iter()
+        .map(|(major_idx, grain)| baking_food(*major_idx, *grain))
+        .sum()
+}
+
+#[must_use]
+pub fn allocation_grain(allocation: &BakingAllocation) -> usize {
+    allocation.iter().map(|(_, grain)| grain).sum()
+}
+
+/// All ways of baking between 1 and `num_grain` grain on the owned baking improvements
+#[must_use]
+pub fn baking_allocations(cards: &[bool], num_grain: usize) -> Vec<BakingAllocation> {
+    let owned = BAKING_IMPROVEMENTS_INDICES
+        .iter()
+        .filter(|&&idx| cards[idx])
+        .copied()
+        .collect::<Vec<usize>>();
+
+    let mut ret: Vec<BakingAllocation> = vec![Vec::new()];
+    for major_idx in owned {
+        let mut next = Vec::new();
+        for allocation in &ret {
+            let left = num_grain - allocation_grain(allocation);
+            let max_grain = baking_capacity(major_idx).map_or(left, |cap| cap.min(left));
+            for grain in 0..=max_grain {
+                let mut extended = allocation.clone();
+                if grain > 0 {
+                    extended.push((major_idx, grain));
+                }
+                next.push(extended);
+            }
+        }
+        ret = next;
+    }
+    ret.retain(|allocation| !allocation.is_empty());
+    ret
+}
+
+/// The allocation of exactly `num_grain` grain that yields the most food.
+/// Fills the improvements with the best conversion rate first. Returns None if the owned improvements cannot bake that much grain at once.
+#[must_use]
+pub fn best_baking_allocation(cards: &[bool], num_grain: usize) -> Option<BakingAllocation> {
+    let mut owned = BAKING_IMPROVEMENTS_INDICES
+        .iter()
+        .filter(|&&idx| cards[idx])
+        .copied()
+        .collect::<Vec<usize>>();
+    // Stable sort keeps the lower card index first among improvements with the same rate
+    owned.sort_by_key(|&idx| std::cmp::Reverse(baking_food(idx, 1)));
+
+    let mut ret = Vec::new();
+    let mut left = num_grain;
+    for major_idx in owned {
+        if left == 0 {
+            break;
+        }
+        let grain = baking_capacity(major_idx).map_or(left, |cap| cap.min(left));
+        if grain > 0 {
+            ret.push((major_idx, grain));
+            left -= grain;
+        }
+    }
+
+    if left > 0 || ret.is_empty() {
+        return None;
+    }
+    ret.sort_unstable();
+    Some(ret)
+}
@@ -88,6 +88,14 @@ pub fn print_resources(state: &State, player_idx: usize) -> String {
             ret.push_str(&format!("\n{}", CARD_NAMES[i]));
         }
     }
+
+    for goods in state.upcoming_goods(player_idx) {
+        ret.push_str(&format!(
+            "\nR{:2} :{}",
+            goods.round,
+            format_resources(&goods.resources)
+        ));
+    }
     ret
 }
 
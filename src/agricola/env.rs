@@ -0,0 +1,299 @@
+use super::action_index::num_global_actions;
+use super::actions::Action;
+use super::algorithms::PlayerType;
+use super::features::num_features;
+use super::state::{State, MAX_NUM_PLAYERS};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// What the agent is rewarded for. Rewards are only given on the step that ends the game, every other step gives 0.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Reward {
+    /// The final score of the agent
+    #[default]
+    Score,
+    /// The margin of victory or defeat, as in `State::fitness`
+    Margin,
+    /// 1 for a win, -1 for a loss and 0 when the best score is shared
+    WinLoss,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvConfig {
+    pub num_players: usize,
+    /// The seat of the agent, the other seats play weighted random moves
+    pub player_idx: usize,
+    pub reward: Reward,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        Self {
+            num_players: 2,
+            player_idx: 0,
+            reward: Reward::default(),
+        }
+    }
+}
+
+/// A gym style environment for training agents. The environment plays chance outcomes, the opponents
+/// and the moves that are the agent's only choice, so every step is a decision of the agent.
+/// Action ids are the global indices of the moves, see `Action::global_idx`.
+pub struct Env {
+    config: EnvConfig,
+    state: State,
+    // Legal moves of the agent, their action ids in the same order and the move of each id
+    moves: Vec<Action>,
+    action_ids: Vec<usize>,
+    move_of_id: HashMap<usize, usize>,
+    rng: ChaCha8Rng,
+}
+
+impl Env {
+    /// # Errors
+    /// If the number of players or the seat of the agent is out of range
+    pub fn new(config: EnvConfig) -> Result<Self, String> {
+        if config.num_players == 0 || config.num_players > MAX_NUM_PLAYERS {
+            return Err(format!("Games have 1 to {MAX_NUM_PLAYERS} players"));
+        }
+        if config.player_idx >= config.num_players {
+            return Err(format!(
+                "Seat {} is not in a game of {} players",
+                config.player_idx, config.num_players
+            ));
+        }
+        let mut env = Self {
+            config,
+            state: State::with_seed(&Self::player_types(&config), 0).unwrap(),
+            moves: Vec::new(),
+            action_ids: Vec::new(),
+            move_of_id: HashMap::new(),
+            rng: ChaCha8Rng::seed_from_u64(0),
+        };
+        env.reset(0);
+        Ok(env)
+    }
+
+    // Every seat is played from outside the AIs of the crate, the agent through `step` and the opponents by `advance`
+    fn player_types(config: &EnvConfig) -> Vec<PlayerType> {
+        vec![PlayerType::Human; config.num_players]
+    }
+
+    /// Starts a new game, returns the first observation of the agent
+    /// # Panics
+    /// Never, the configuration is checked when the environment is made
+    pub fn reset(&mut self, seed: u64) -> Vec<f32> {
+        self.state = State::with_seed(&Self::player_types(&self.config), seed).unwrap();
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+        // Keep the opponents' moves apart from the chance outcomes drawn by the state
+        self.rng.set_stream(1);
+        self.advance();
+        self.observation()
+    }
+
+    /// Plays the legal move `action_id` for the agent, then everything up to the agent's next decision.
+    /// Returns the observation, the reward and whether the game is over.
+    /// # Errors
+    /// If the game is over or `action_id` is not a legal move
+    pub fn step(&mut self, action_id: usize) -> Result<(Vec<f32>, f64, bool), String> {
+        if self.moves.is_empty() {
+            return Err(String::from("The game is over, reset the environment"));
+        }
+        let action = self
+            .move_of_id
+            .get(&action_id)
+            .map(|i| self.moves[*i].clone())
+            .ok_or_else(|| format!("Action {action_id} is not legal"))?;
+        action.apply_choice(&mut self.state);
+        self.advance();
+        let done = self.is_done();
+        let reward = if done { self.final_reward() } else { 0.0 };
+        Ok((self.observation(), reward, done))
+    }
+
+    /// The legal action ids, in the order of `legal_moves` and empty once the game is over.
+    /// Ids are below `num_actions`, which is too large to hand out a mask on every step.
+    #[must_use]
+    pub fn legal_action_ids(&self) -> &[usize] {
+        &self.action_ids
+    }
+
+    /// Number of action ids
+    #[must_use]
+    pub fn num_actions(&self) -> usize {
+        num_global_actions(self.state.ruleset())
+    }
+
+    /// Length of every observation tensor
+    #[must_use]
+    pub fn observation_size(&self) -> usize {
+        num_features(self.state.ruleset())
+    }
+
+    /// The moves behind the legal action ids, in the order of `legal_action_ids`
+    #[must_use]
+    pub fn legal_moves(&self) -> &[Action] {
+        &self.moves
+    }
+
+    #[must_use]
+    pub fn is_done(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    #[must_use]
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    #[must_use]
+    pub fn config(&self) -> &EnvConfig {
+        &self.config
+    }
+
+    /// The observation tensor of the agent, the features of the state from its seat, see `State::features`
+    #[must_use]
+    pub fn observation(&self) -> Vec<f32> {
+        self.state.features(self.config.player_idx)
+    }
+
+    fn final_reward(&self) -> f64 {
+        let player_idx = self.config.player_idx;
+        match self.config.reward {
+            Reward::Score => self.state.scores()[player_idx],
+            Reward::Margin => self.state.fitness()[player_idx],
+            Reward::WinLoss => {
+                let margin = self.state.fitness()[player_idx];
+                if self.config.num_players == 1 || margin.abs() < f64::EPSILON {
+                    0.0
+                } else {
+                    margin.signum()
+                }
+            }
+        }
+    }
+
+    // Resolve chance nodes, opponent moves and forced moves until the agent has a decision to make
+    fn advance(&mut self) {
+        loop {
+            if self.state.is_chance_node() {
+                self.state.sample_chance().apply_choice(&mut self.state);
+                continue;
+            }
+            let mut choices = Action::next_choices(&self.state);
+            if choices.is_empty() {
+                self.set_moves(Vec::new());
+                return;
+            }
+            if self.state.current_player_idx == self.config.player_idx && choices.len() > 1 {
+                self.set_moves(choices.into_iter().map(|(action, _)| action).collect());
+                return;
+            }
+            let action = if choices.len() == 1 {
+                choices.pop().unwrap().0
+            } else {
+                self.weighted_random(choices)
+            };
+            action.apply_choice(&mut self.state);
+        }
+    }
+
+    // Indexes the moves of the agent once, so steps look their id up
+    fn set_moves(&mut self, moves: Vec<Action>) {
+        let ruleset = self.state.ruleset();
+        self.action_ids = moves
+            .iter()
+            .map(|action| action.global_idx(ruleset))
+            .collect();
+        self.move_of_id = self
+            .action_ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (*id, i))
+            .collect();
+        self.moves = moves;
+    }
+
+    fn weighted_random(&mut self, mut choices: Vec<(Action, f64)>) -> Action {
+        let total_weight: f64 = choices.iter().map(|(_, weight)| weight).sum();
+        if total_weight <= 0.0 {
+            // No move is favoured, pick any
+            let i = self.rng.gen_range(0..choices.len());
+            return choices.swap_remove(i).0;
+        }
+        let mut weight_left = self.rng.gen_range(0.0..total_weight);
+        let num_choices = choices.len();
+        for (i, (action, weight)) in choices.into_iter().enumerate() {
+            if weight_left < weight || i + 1 == num_choices {
+                return action;
+            }
+            weight_left -= weight;
+        }
+        unreachable!()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_plays_a_game() {
+        let config = EnvConfig {
+            num_players: 3,
+            player_idx: 1,
+            reward: Reward::WinLoss,
+        };
+        let mut env = Env::new(config).unwrap();
+        let observation = env.reset(11);
+        assert_eq!(observation.len(), env.observation_size());
+        assert_eq!(env.state().current_player_idx, 1);
+
+        let mut rng = ChaCha8Rng::seed_from_u64(11);
+        let mut steps = 0;
+        let reward = loop {
+            let legal = env.legal_action_ids().to_vec();
+            assert_eq!(legal.len(), env.legal_moves().len());
+            for (id, action) in legal.iter().zip(env.legal_moves()) {
+                assert!(*id < env.num_actions());
+                assert_eq!(action.global_idx(env.state().ruleset()), *id);
+            }
+            assert!(env.step(env.num_actions()).is_err());
+
+            let action_id = legal[rng.gen_range(0..legal.len())];
+            let (observation, reward, done) = env.step(action_id).unwrap();
+            assert_eq!(observation.len(), env.observation_size());
+            steps += 1;
+            if done {
+                break reward;
+            }
+            assert!(reward.abs() < f64::EPSILON);
+        };
+        assert!(steps > 14);
+        assert!([-1.0, 0.0, 1.0].contains(&reward));
+        assert!(env.legal_action_ids().is_empty());
+        assert!(env.step(0).is_err());
+
+        // Same seed, same game
+        let first = env.reset(5);
+        assert_eq!(env.reset(5), first);
+        assert!(Env::new(EnvConfig {
+            num_players: 2,
+            player_idx: 2,
+            reward: Reward::Score,
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_weighted_random_without_weights() {
+        let mut env = Env::new(EnvConfig::default()).unwrap();
+        let choices = vec![(Action::UseSpace(0), 0.0), (Action::UseSpace(1), 0.0)];
+        for _ in 0..10 {
+            let action = env.weighted_random(choices.clone());
+            assert!(choices.iter().any(|(choice, _)| *choice == action));
+        }
+    }
+}
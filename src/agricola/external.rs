@@ -0,0 +1,192 @@
+use super::actions::Action;
+use super::state::State;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+/// A player whose moves come from a child process speaking the line based engine protocol of `src/bin/engine.rs`.
+/// Only a few commands are used : `agricola` to start, `position` and `go` for every move, and `quit`.
+/// Any program answering those can sit at the table, e.g. a Python prototype or an older build of this engine.
+pub struct ExternalEngine {
+    child: Child,
+    stdin: ChildStdin,
+    // Lines of the engine's stdout, read on their own thread so a search can be waited for without blocking
+    lines: Receiver<String>,
+    // Searches started and not answered yet, the replies of all but the last are stale
+    searches: usize,
+}
+
+// The line if it is the expected reply. Other lines, such as `info` lines during a search, are skipped.
+fn check_reply(line: &str, reply: &str) -> Result<Option<String>, String> {
+    let line = line.trim();
+    if let Some(error) = line.strip_prefix("error") {
+        return Err(format!("The engine replied : {}", error.trim()));
+    }
+    Ok((line.split_whitespace().next() == Some(reply)).then(|| line.to_string()))
+}
+
+impl ExternalEngine {
+    /// Starts `program` with `args`, passed as they are without going through a shell, and waits for it to be ready
+    /// # Errors
+    /// If the program cannot be started or does not answer the protocol
+    pub fn spawn(program: &str, args: &[String]) -> Result<Self, String> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Could not start '{program}' : {e}"))?;
+        let stdin = child.stdin.take().ok_or("The engine has no stdin")?;
+        let stdout = BufReader::new(child.stdout.take().ok_or("The engine has no stdout")?);
+
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in stdout.lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = Self {
+            child,
+            stdin,
+            lines,
+            searches: 0,
+        };
+        engine.send("agricola")?;
+        engine.read_until("agricolaok")?;
+        Ok(engine)
+    }
+
+    /// Asks the engine for its move in `state`, searching for `samples` simulated games, and waits for the answer
+    /// # Errors
+    /// If the engine fails, stops answering or replies with a move that is not legal in `state`
+    pub fn choose_move(&mut self, state: &State, samples: usize) -> Result<Action, String> {
+        self.start_move(state, samples)?;
+        loop {
+            let line = self
+                .lines
+                .recv()
+                .map_err(|_| String::from("The engine exited"))?;
+            if let Some(action) = self.check_move(&line, state)? {
+                return Ok(action);
+            }
+        }
+    }
+
+    /// Starts a search for the move in `state` without waiting for it, the move is collected with `poll_move`.
+    /// The engine is sent what the player to move observes, the seed deciding the round cards to come stays here.
+    /// A search started before is forgotten, its move is skipped when it arrives.
+    /// # Errors
+    /// If the engine cannot be written to
+    pub fn start_move(&mut self, state: &State, samples: usize) -> Result<(), String> {
+        let observed = state.observation(state.current_player_idx).determinize(0);
+        self.send(&format!("position {}", observed.to_position_str()))?;
+        self.send(&format!("go samples {samples}"))?;
+        self.searches += 1;
+        Ok(())
+    }
+
+    /// The move of the last search started in `state`, None while the engine is still thinking
+    /// # Errors
+    /// If the engine fails, exits or replies with a move that is not legal in `state`
+    pub fn poll_move(&mut self, state: &State) -> Result<Option<Action>, String> {
+        loop {
+            let line = match self.lines.try_recv() {
+                Ok(line) => line,
+                Err(TryRecvError::Empty) => return Ok(None),
+                Err(TryRecvError::Disconnected) => return Err(String::from("The engine exited")),
+            };
+            if let Some(action) = self.check_move(&line, state)? {
+                return Ok(Some(action));
+            }
+        }
+    }
+
+    // The move in `line` if it answers the last search
+    fn check_move(&mut self, line: &str, state: &State) -> Result<Option<Action>, String> {
+        let Some(line) = check_reply(line, "bestmove")? else {
+            return Ok(None);
+        };
+        self.searches = self.searches.saturating_sub(1);
+        if self.searches > 0 {
+            return Ok(None);
+        }
+        let notation = line.trim_start_matches("bestmove").trim();
+        Action::from_notation(notation, state).map(Some)
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), String> {
+        writeln!(self.stdin, "{command}")
+            .and_then(|()| self.stdin.flush())
+            .map_err(|e| format!("Could not write to the engine : {e}"))
+    }
+
+    fn read_until(&mut self, reply: &str) -> Result<String, String> {
+        loop {
+            let line = self
+                .lines
+                .recv()
+                .map_err(|_| String::from("The engine exited"))?;
+            if let Some(line) = check_reply(&line, reply)? {
+                return Ok(line);
+            }
+        }
+    }
+}
+
+impl Drop for ExternalEngine {
+    fn drop(&mut self) {
+        // The engine may already be gone, there is nothing more to do if it does not quit cleanly
+        let _ = self.send("quit");
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::agricola::algorithms::PlayerType;
+    use crate::agricola::game::Game;
+
+    // A stand-in engine written in shell
+    fn spawn_script(script: &str) -> Result<ExternalEngine, String> {
+        ExternalEngine::spawn("sh", &[String::from("-c"), String::from(script)])
+    }
+
+    #[test]
+    fn test_external_engine() {
+        let game = Game::with_seed(&[PlayerType::External, PlayerType::Human], 47).unwrap();
+        let state = game.state();
+
+        // Always plays the first action space
+        let mut engine = spawn_script(
+            "while read c r; do case $c in agricola) echo agricolaok;; position) echo ok;; \
+             go) echo info move U0; echo bestmove U0;; quit) exit;; esac; done",
+        )
+        .unwrap();
+        assert_eq!(engine.choose_move(state, 10), Ok(Action::UseSpace(0)));
+
+        // Without waiting, the first search is overtaken by the second and only its move is returned
+        engine.start_move(state, 10).unwrap();
+        engine.start_move(state, 10).unwrap();
+        let action = loop {
+            if let Some(action) = engine.poll_move(state).unwrap() {
+                break action;
+            }
+        };
+        assert_eq!(action, Action::UseSpace(0));
+        assert_eq!(engine.searches, 0);
+
+        let mut failing =
+            spawn_script("read c; echo agricolaok; read c; echo error bad position").unwrap();
+        assert!(failing.choose_move(state, 10).is_err());
+        assert!(spawn_script("exit").is_err());
+        assert!(ExternalEngine::spawn("/nonexistent/engine", &[]).is_err());
+    }
+}
@@ -1,5 +1,7 @@
 use derivative::Derivative;
+use serde::{Deserialize, Serialize};
 
+use super::actions::RuleError;
 use super::fencing::{
     best_fence_options, get_existing_pasture_capacities, get_existing_pastures, PastureConfig,
 };
@@ -19,7 +21,7 @@ const PASTURE_ORDER: [usize; 13] = [14, 9, 13, 8, 4, 3, 12, 7, 2, 11, 6, 1, 0];
 // Try and place one stable each in pastures without stables
 const STABLE_ORDER: [usize; 13] = [14, 9, 4, 13, 8, 3, 12, 7, 2, 11, 6, 1, 0];
 
-#[derive(Debug, Copy, Clone, Hash, PartialEq)]
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Serialize, Deserialize)]
 pub enum Seed {
     Grain,
     Vegetable,
@@ -27,7 +29,10 @@ pub enum Seed {
 
 type ContainsStable = bool;
 
-#[derive(Copy, Clone, Debug, Default, Hash, PartialEq)]
+/// Seeds to sow on specific empty fields. Empty fields not in the plan stay empty.
+pub type SowingPlan = Vec<(usize, Seed)>;
+
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Serialize, Deserialize)]
 pub enum FarmyardSpace {
     #[default]
     Empty,
@@ -61,7 +66,7 @@ pub const NEIGHBOR_SPACES: [[Option<usize>; 4]; NUM_FARMYARD_SPACES] = [
     [Some(9), None, Some(13), None],
 ];
 
-#[derive(Derivative, Debug, Clone, Hash)]
+#[derive(Derivative, Debug, Clone, Hash, PartialEq, Serialize, Deserialize)]
 pub struct Farm {
     pub farmyard_spaces: [FarmyardSpace; NUM_FARMYARD_SPACES],
     pub fences_used: usize,
@@ -159,10 +164,16 @@ impl Farm {
     }
 
     #[must_use]
-    pub fn fencing_options(&self, cache: &[PastureConfig], wood: usize) -> Vec<PastureConfig> {
-        if self.fences_used >= MAX_FENCES {
+    pub fn fencing_options(
+        &self,
+        cache: &[PastureConfig],
+        wood: usize,
+        max_fences: usize,
+    ) -> Vec<PastureConfig> {
+        if self.fences_used >= max_fences {
             return Vec::new();
         }
+        let wood = wood.min(max_fences - self.fences_used);
         best_fence_options(cache, self.fences_used, wood, &PASTURE_ORDER)
     }
 
@@ -279,41 +290,52 @@ impl Farm {
             .collect()
     }
 
-    /// # Panics
-    /// If cannot add field
-    pub fn add_field(&mut self, idx: usize) {
-        assert!(self.farmyard_spaces[idx] == FarmyardSpace::Empty);
+    // The space at `idx`, if there is one
+    fn space(&self, idx: usize) -> Result<&FarmyardSpace, RuleError> {
+        self.farmyard_spaces
+            .get(idx)
+            .ok_or(RuleError::InvalidFarmyardIndex(idx))
+    }
+
+    // The space at `idx`, if it is empty
+    fn empty_space(&self, idx: usize) -> Result<(), RuleError> {
+        match self.space(idx)? {
+            FarmyardSpace::Empty => Ok(()),
+            _ => Err(RuleError::SpaceOccupied(idx)),
+        }
+    }
+
+    /// # Errors
+    /// If there is no empty space at `idx`
+    pub fn add_field(&mut self, idx: usize) -> Result<(), RuleError> {
+        self.empty_space(idx)?;
         self.farmyard_spaces[idx] = FarmyardSpace::Field(None);
+        Ok(())
     }
 
-    /// # Panics
-    /// If cannot build room
-    pub fn build_room(&mut self, idx: usize) {
-        assert!(self.farmyard_spaces[idx] == FarmyardSpace::Empty);
+    /// # Errors
+    /// If there is no empty space at `idx`
+    pub fn build_room(&mut self, idx: usize) -> Result<(), RuleError> {
+        self.empty_space(idx)?;
         self.farmyard_spaces[idx] = FarmyardSpace::Room;
+        Ok(())
     }
 
-    /// # Panics
-    /// If cannot build stable
-    pub fn build_stable(&mut self, idx: usize) {
-        let available = matches!(
-            self.farmyard_spaces[idx],
-            FarmyardSpace::Empty | FarmyardSpace::FencedPasture(false, _)
-        );
-        assert!(available);
-        assert!(self.can_build_stable());
-
-        match self.farmyard_spaces[idx] {
+    /// # Errors
+    /// If there is no empty space or pasture without a stable at `idx`
+    pub fn build_stable(&mut self, idx: usize) -> Result<(), RuleError> {
+        match *self.space(idx)? {
             FarmyardSpace::Empty => self.farmyard_spaces[idx] = FarmyardSpace::UnfencedStable,
             FarmyardSpace::FencedPasture(false, pasture_idx) => {
                 self.farmyard_spaces[idx] = FarmyardSpace::FencedPasture(true, pasture_idx);
             }
-            _ => (),
+            _ => return Err(RuleError::SpaceOccupied(idx)),
         }
+        Ok(())
     }
 
     #[must_use]
-    pub fn can_build_stable(&self) -> bool {
+    pub fn can_build_stable(&self, max_stables: usize) -> bool {
         let mut num_stables = 0;
         let mut candidate_spaces = 0;
         for fs in &self.farmyard_spaces {
@@ -328,13 +350,26 @@ impl Farm {
             }
         }
 
-        if candidate_spaces > 0 && num_stables < MAX_STABLES {
+        if candidate_spaces > 0 && num_stables < max_stables {
             return true;
         }
 
         false
     }
 
+    #[must_use]
+    pub fn num_stables(&self) -> usize {
+        self.farmyard_spaces
+            .iter()
+            .filter(|fs| {
+                matches!(
+                    fs,
+                    FarmyardSpace::UnfencedStable | FarmyardSpace::FencedPasture(true, _)
+                )
+            })
+            .count()
+    }
+
     #[must_use]
     pub fn can_sow(&self) -> bool {
         self.farmyard_spaces
@@ -342,20 +377,82 @@ impl Farm {
             .any(|f| matches!(f, FarmyardSpace::Field(None)))
     }
 
-    /// # Panics
-    /// If there are no empty fields
-    pub fn sow_field(&mut self, seed: &Seed) {
-        assert!(self.can_sow());
-        let opt_empty_field = self
-            .farmyard_spaces
-            .iter_mut()
-            .find(|f| matches!(f, FarmyardSpace::Field(None)));
-        if let Some(field) = opt_empty_field {
-            match *seed {
-                Seed::Grain => *field = FarmyardSpace::Field(Some((Seed::Grain, 3))),
-                Seed::Vegetable => *field = FarmyardSpace::Field(Some((Seed::Vegetable, 2))),
+    #[must_use]
+    pub fn empty_field_indices(&self) -> Vec<usize> {
+        (0..NUM_FARMYARD_SPACES)
+            .filter(|&i| matches!(self.farmyard_spaces[i], FarmyardSpace::Field(None)))
+            .collect()
+    }
+
+    /// All ways of sowing up to `num_grain` grain and `num_veg` vegetables : each empty field gets grain, vegetables or stays empty.
+    /// Plans list the sown fields in index order.
+    #[must_use]
+    pub fn sowing_plans(&self, num_grain: usize, num_veg: usize) -> Vec<SowingPlan> {
+        let mut ret = vec![Vec::new()];
+        for idx in self.empty_field_indices() {
+            let mut next = Vec::new();
+            for plan in ret {
+                let num_grain_sown = plan.iter().filter(|(_, seed)| *seed == Seed::Grain).count();
+                let num_veg_sown = plan.len() - num_grain_sown;
+                if num_grain_sown < num_grain {
+                    let mut with_grain = plan.clone();
+                    with_grain.push((idx, Seed::Grain));
+                    next.push(with_grain);
+                }
+                if num_veg_sown < num_veg {
+                    let mut with_veg = plan.clone();
+                    with_veg.push((idx, Seed::Vegetable));
+                    next.push(with_veg);
+                }
+                next.push(plan);
+            }
+            ret = next;
+        }
+        ret.retain(|plan| !plan.is_empty());
+        ret
+    }
+
+    /// One plan for each (grain, vegetable) split, filling the empty fields in index order, grain first.
+    /// Empty fields are interchangeable, so these are all the plans that lead to different farms.
+    #[must_use]
+    pub fn distinct_sowing_plans(&self, num_grain: usize, num_veg: usize) -> Vec<SowingPlan> {
+        let num_fields = self.empty_field_indices().len();
+        let mut ret = Vec::new();
+        for grain in 0..=num_grain.min(num_fields) {
+            for veg in 0..=num_veg.min(num_fields - grain) {
+                if grain + veg > 0 {
+                    ret.push(self.distinct_sowing_plan(grain, veg));
+                }
             }
         }
+        ret
+    }
+
+    /// The plan among `distinct_sowing_plans` that sows `num_grain` grain and `num_veg` vegetables
+    #[must_use]
+    pub fn distinct_sowing_plan(&self, num_grain: usize, num_veg: usize) -> SowingPlan {
+        self.empty_field_indices()
+            .into_iter()
+            .take(num_grain + num_veg)
+            .enumerate()
+            .map(|(i, idx)| {
+                if i < num_grain {
+                    (idx, Seed::Grain)
+                } else {
+                    (idx, Seed::Vegetable)
+                }
+            })
+            .collect()
+    }
+
+    /// # Errors
+    /// If there is no empty field at `idx`
+    pub fn sow_field(&mut self, idx: usize, seed: Seed, amount: usize) -> Result<(), RuleError> {
+        if *self.space(idx)? != FarmyardSpace::Field(None) {
+            return Err(RuleError::SpaceOccupied(idx));
+        }
+        self.farmyard_spaces[idx] = FarmyardSpace::Field(Some((seed, amount)));
+        Ok(())
     }
 
     pub fn harvest_fields(&mut self) -> Vec<Seed> {
@@ -0,0 +1,192 @@
+use super::card::NUM_CARDS;
+use super::farm::{FarmyardSpace, NUM_FARMYARD_SPACES};
+use super::fencing::{pasture_capacities, MAX_PASTURES};
+use super::flag::NUM_FLAGS;
+use super::quantity::{NUM_QUANTITIES, NUM_RESOURCES};
+use super::ruleset::Ruleset;
+use super::state::{State, MAX_NUM_PLAYERS};
+
+/// Farmyard space kinds in the one-hot : empty, room, field, unfenced stable, fenced pasture
+pub const NUM_FARMYARD_KINDS: usize = 5;
+/// Features of one seat
+pub const PLAYER_FEATURES: usize = 3
+    + NUM_QUANTITIES
+    + NUM_FLAGS
+    + NUM_CARDS
+    + NUM_FARMYARD_SPACES * NUM_FARMYARD_KINDS
+    + MAX_PASTURES;
+/// Features of one action space
+pub const ACTION_SPACE_FEATURES: usize = 1 + MAX_NUM_PLAYERS + NUM_RESOURCES;
+/// Features of the round and the harvest
+pub const TIMING_FEATURES: usize = 4;
+
+/// Length of every feature vector of games played with `ruleset`
+#[must_use]
+pub fn num_features(ruleset: &Ruleset) -> usize {
+    MAX_NUM_PLAYERS * PLAYER_FEATURES
+        + ruleset.num_action_spaces() * ACTION_SPACE_FEATURES
+        + TIMING_FEATURES
+}
+
+// What each quantity is divided by, a typical high amount so most features stay in 0..=1
+const QUANTITY_SCALES: [f32; NUM_QUANTITIES] = [
+    10.0, // Wood
+    10.0, // Clay
+    6.0,  // Stone
+    10.0, // Food
+    6.0,  // Reed
+    8.0,  // Grain
+    4.0,  // Vegetable
+    8.0,  // Sheep
+    6.0,  // Boar
+    4.0,  // Cattle
+    5.0,  // Adult members
+    5.0,  // Children
+    5.0,  // Members placed this round
+    5.0,  // Rooms
+    5.0,  // Fields
+    4.0,  // Pastures
+    13.0, // Pasture spaces
+    4.0,  // Unfenced stables
+    4.0,  // Fenced stables
+    3.0,  // Begging tokens
+];
+const PASTURE_CAPACITY_SCALE: f32 = 16.0;
+
+impl State {
+    /// The position as numbers for learned evaluators and policies, `num_features` long and the same layout in every game
+    /// with the same ruleset.
+    /// Seats are rotated over the players so `player_idx` comes first and the players after it follow in turn order,
+    /// absent seats are left as zeros at the end. Only what every player can see
+    /// is used, the order of the round cards to come stays hidden.
+    ///
+    /// Layout :
+    /// - `MAX_NUM_PLAYERS` seats of `PLAYER_FEATURES` : whether the seat is in the game, is to move and is the starting player,
+    ///   the quantities divided by a typical high amount of each, the flags, the cards, a one-hot of the kind of
+    ///   each farmyard space and the capacity of each pasture
+    /// - every action space of the ruleset, `ACTION_SPACE_FEATURES` each : whether the space is revealed, a one-hot of the seat
+    ///   occupying it and the accumulated resources, divided like the quantities
+    /// - `TIMING_FEATURES` : the round, whether it ends in a harvest, whether that harvest is done and the rounds
+    ///   to the next harvest, in parts of the whole game
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn features(&self, player_idx: usize) -> Vec<f32> {
+        let ruleset = self.ruleset();
+        let mut ret = Vec::with_capacity(num_features(ruleset));
+        let num_players = self.num_players;
+        let seat = |idx: usize| (idx + num_players - player_idx) % num_players;
+        let as_f32 = |b: bool| if b { 1.0 } else { 0.0 };
+
+        for offset in 0..num_players {
+            let idx = (player_idx + offset) % num_players;
+            ret.push(1.0);
+            ret.push(as_f32(idx == self.current_player_idx));
+            ret.push(as_f32(idx == self.starting_player_idx));
+            ret.extend(
+                self.player_quantities(idx)
+                    .iter()
+                    .zip(QUANTITY_SCALES)
+                    .map(|(q, scale)| *q as f32 / scale),
+            );
+            ret.extend(self.player_flags(idx).iter().map(|f| as_f32(*f)));
+            ret.extend(self.player_cards(idx).iter().map(|c| as_f32(*c)));
+            let farmyard_spaces = &self.player_farm(idx).farmyard_spaces;
+            for space in farmyard_spaces {
+                let kind = match space {
+                    FarmyardSpace::Empty => 0,
+                    FarmyardSpace::Room => 1,
+                    FarmyardSpace::Field(_) => 2,
+                    FarmyardSpace::UnfencedStable => 3,
+                    FarmyardSpace::FencedPasture(..) => 4,
+                };
+                let mut one_hot = [0.0; NUM_FARMYARD_KINDS];
+                one_hot[kind] = 1.0;
+                ret.extend(one_hot);
+            }
+            ret.extend(
+                pasture_capacities(farmyard_spaces)
+                    .iter()
+                    .map(|c| *c as f32 / PASTURE_CAPACITY_SCALE),
+            );
+        }
+        ret.extend(vec![0.0; (MAX_NUM_PLAYERS - num_players) * PLAYER_FEATURES]);
+
+        let revealed = self.revealed_action_spaces();
+        for action_space_idx in 0..ruleset.num_action_spaces() {
+            ret.push(as_f32(revealed.contains(&action_space_idx)));
+            let mut occupied = [0.0; MAX_NUM_PLAYERS];
+            if let Some(idx) = self.occupied[action_space_idx] {
+                occupied[seat(idx)] = 1.0;
+            }
+            ret.extend(occupied);
+            ret.extend(
+                self.accumulated_resources[action_space_idx]
+                    .iter()
+                    .zip(QUANTITY_SCALES)
+                    .map(|(r, scale)| *r as f32 / scale),
+            );
+        }
+
+        let num_rounds = ruleset.num_rounds() as f32;
+        let next_harvest = ruleset
+            .harvest_rounds
+            .iter()
+            .filter(|round| {
+                **round > self.current_round
+                    || (**round == self.current_round && !self.harvest_done)
+            })
+            .min();
+        ret.push(self.current_round as f32 / num_rounds);
+        ret.push(as_f32(ruleset.is_harvest_round(self.current_round)));
+        ret.push(as_f32(self.harvest_done));
+        ret.push(next_harvest.map_or(0.0, |round| {
+            (round - self.current_round) as f32 / num_rounds
+        }));
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agricola::actions::Action;
+    use crate::agricola::algorithms::PlayerType;
+
+    #[test]
+    fn test_features_layout() {
+        let mut state = State::with_seed(&[PlayerType::Human; 3], 7).unwrap();
+        while state.current_round < 4 {
+            let choices = Action::next_choices(&state);
+            choices[0].0.apply_choice(&mut state);
+        }
+
+        for player_idx in 0..3 {
+            let features = state.features(player_idx);
+            assert_eq!(features.len(), num_features(state.ruleset()));
+            assert!(features.iter().all(|f| f.is_finite() && *f >= 0.0));
+            // The fourth seat is empty in a game of three, whoever looks at it
+            assert!(
+                features[3 * PLAYER_FEATURES..MAX_NUM_PLAYERS * PLAYER_FEATURES]
+                    .iter()
+                    .all(|f| *f == 0.0)
+            );
+            assert_eq!(features[2 * PLAYER_FEATURES], 1.0);
+        }
+
+        // The next seat of a player is the first seat of the player after it, player 0 after the last player
+        let features = (0..3).map(|idx| state.features(idx)).collect::<Vec<_>>();
+        for player_idx in 0..3 {
+            assert_eq!(
+                features[player_idx][PLAYER_FEATURES..2 * PLAYER_FEATURES],
+                features[(player_idx + 1) % 3][..PLAYER_FEATURES]
+            );
+        }
+        let first = &features[0];
+
+        // Round 4 ends in the first harvest
+        let timing = &first[first.len() - TIMING_FEATURES..];
+        assert_eq!(timing[1], 1.0);
+        assert_eq!(timing[2], 0.0);
+        assert!(timing[3].abs() < f32::EPSILON);
+    }
+}
@@ -0,0 +1,193 @@
+use super::card::{anytime_exchanges, harvest_exchanges};
+use super::quantity::{
+    new_res, Food, Grain, Quantities, Quantity, ResourceExchange, Resources, Vegetable,
+    NUM_RESOURCES,
+};
+use serde::{Deserialize, Serialize};
+
+/// A way of paying food during the harvest. Each exchange is used the given number of times before paying, any food still missing is begged for.
+#[derive(Clone, Debug, Hash, PartialEq, Serialize, Deserialize)]
+pub struct FeedingPlan {
+    pub exchanges: Vec<(ResourceExchange, usize)>,
+    pub begging_tokens: usize,
+}
+
+impl FeedingPlan {
+    #[must_use]
+    pub fn food_gained(&self) -> usize {
+        self.exchanges
+            .iter()
+            .map(|(ex, times)| ex.num_to * times)
+            .sum()
+    }
+
+    #[must_use]
+    pub fn spent(&self) -> Resources {
+        let mut ret = new_res();
+        for (ex, times) in &self.exchanges {
+            ret[ex.from] += ex.num_from * times;
+        }
+        ret
+    }
+}
+
+// Gives a higher food rate than `other`
+fn better_rate(ex: &ResourceExchange, other: &ResourceExchange) -> bool {
+    ex.num_to * other.num_from > other.num_to * ex.num_from
+}
+
+// The best exchange to food for each good, along with the number of times it can be used this harvest
+fn feeding_options(quantities: &Quantities, cards: &[bool]) -> Vec<(ResourceExchange, usize)> {
+    let mut best: [Option<(ResourceExchange, usize)>; NUM_RESOURCES] = Default::default();
+    let mut consider = |ex: ResourceExchange, once_per_harvest: bool| {
+        let mut uses = quantities[ex.from] / ex.num_from;
+        if once_per_harvest {
+            uses = uses.min(1);
+        }
+        if uses == 0 || ex.to != Food.index() {
+            return;
+        }
+        let replace = match &best[ex.from] {
+            Some((current, _)) => better_rate(&ex, current),
+            None => true,
+        };
+        if replace {
+            let from = ex.from;
+            best[from] = Some((ex, uses));
+        }
+    };
+
+    // Raw grain and vegetables can always be eaten
+    for raw in [Grain.index(), Vegetable.index()] {
+        consider(
+            ResourceExchange {
+                from: raw,
+                to: Food.index(),
+                num_from: 1,
+                num_to: 1,
+            },
+            false,
+        );
+    }
+
+    for (idx, owned) in cards.iter().enumerate() {
+        if *owned {
+            for ex in anytime_exchanges(idx) {
+                consider(ex, false);
+            }
+            for ex in harvest_exchanges(idx) {
+                consider(ex, true);
+            }
+        }
+    }
+
+    best.into_iter().flatten().collect()
+}
+
+// Enumerate the number of times each option is used. Stops adding once the missing food is covered, so every covering combination is minimal in the last option used
+fn enumerate_uses(
+    options: &[(ResourceExchange, usize)],
+    food_missing: usize,
+    current: &mut Vec<usize>,
+    out: &mut Vec<Vec<usize>>,
+) {
+    let i = current.len();
+    if i == options.len() || food_missing == 0 {
+        let mut uses = current.clone();
+        uses.resize(options.len(), 0);
+        out.push(uses);
+        return;
+    }
+
+    let (ex, max_uses) = &options[i];
+    let max_useful = food_missing.div_ceil(ex.num_to).min(*max_uses);
+    for times in 0..=max_useful {
+        current.push(times);
+        enumerate_uses(
+            options,
+            food_missing.saturating_sub(times * ex.num_to),
+            current,
+            out,
+        );
+        current.pop();
+    }
+}
+
+/// Pareto optimal ways of paying `food_required` during the harvest.
+/// All plans beg as little as possible. Among those, no plan spends at least as much of every good as another plan.
+#[must_use]
+pub fn feeding_plans(
+    food_required: usize,
+    quantities: &Quantities,
+    cards: &[bool],
+) -> Vec<FeedingPlan> {
+    let food_missing = food_required.saturating_sub(quantities[Food.index()]);
+    let options = feeding_options(quantities, cards);
+
+    let mut all_uses = Vec::new();
+    enumerate_uses(&options, food_missing, &mut Vec::new(), &mut all_uses);
+
+    let plans = all_uses
+        .iter()
+        .map(|uses| {
+            let exchanges = options
+                .iter()
+                .zip(uses.iter())
+                .filter(|(_, &times)| times > 0)
+                .map(|((ex, _), &times)| (ex.clone(), times))
+                .collect::<Vec<_>>();
+            let mut plan = FeedingPlan {
+                exchanges,
+                begging_tokens: 0,
+            };
+            plan.begging_tokens = food_missing.saturating_sub(plan.food_gained());
+            plan
+        })
+        .collect::<Vec<_>>();
+
+    let min_begging = plans.iter().map(|p| p.begging_tokens).min().unwrap_or(0);
+    let candidates = plans
+        .into_iter()
+        .filter(|p| p.begging_tokens == min_begging)
+        .collect::<Vec<_>>();
+
+    let dominates = |a: &FeedingPlan, b: &FeedingPlan| {
+        let (spent_a, spent_b) = (a.spent(), b.spent());
+        spent_a.iter().zip(spent_b.iter()).all(|(x, y)| x <= y) && spent_a != spent_b
+    };
+
+    candidates
+        .iter()
+        .filter(|p| !candidates.iter().any(|other| dominates(other, p)))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agricola::card::{Card, Fireplace1, NUM_CARDS};
+    use crate::agricola::quantity::{Sheep, NUM_QUANTITIES};
+
+    #[test]
+    fn test_feeding_plans_keep_goods() {
+        let mut quantities = [0; NUM_QUANTITIES];
+        quantities[Food.index()] = 1;
+        quantities[Grain.index()] = 2;
+        quantities[Sheep.index()] = 1;
+        let mut cards = [false; NUM_CARDS];
+        cards[Fireplace1.index()] = true;
+
+        // 3 food missing : cooking the sheep and eating one grain is enough, eating both grain would be wasteful
+        let plans = feeding_plans(4, &quantities, &cards);
+        assert_eq!(plans.len(), 1);
+        assert_eq!(plans[0].begging_tokens, 0);
+        assert_eq!(plans[0].spent()[Grain.index()], 1);
+        assert_eq!(plans[0].spent()[Sheep.index()], 1);
+
+        // Without a cooking improvement the sheep cannot be eaten
+        let plans = feeding_plans(4, &quantities, &[false; NUM_CARDS]);
+        assert_eq!(plans.len(), 1);
+        assert_eq!(plans[0].begging_tokens, 1);
+    }
+}
@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, VecDeque};
 
 use super::farm::{FarmyardSpace, MAX_FENCES, NEIGHBOR_SPACES, NUM_FARMYARD_SPACES};
@@ -14,7 +15,7 @@ const STABLE_MULTIPLIER: usize = 2;
 
 pub type Pasture = Vec<usize>;
 
-#[derive(Clone, Debug, Hash)]
+#[derive(Clone, Debug, Hash, PartialEq, Serialize, Deserialize)]
 pub struct PastureConfig {
     pub pastures: Vec<Pasture>,
     pub wood: usize,
@@ -134,7 +135,7 @@ fn combine_pastures(
 const PRIMES: [u64; 5] = [2, 3, 5, 7, 11];
 
 // Hash a multi-pasture configuration based on the number of spaces in each pasture
-fn pasture_config_hash(pastures: &[Pasture]) -> u64 {
+pub(crate) fn pasture_config_hash(pastures: &[Pasture]) -> u64 {
     let mut hash = 1;
     let mut pasture_sizes = Vec::new();
     for p in pastures {
@@ -155,10 +156,10 @@ pub fn pasture_sizes_from_hash(hash: u64) -> Vec<usize> {
     let mut h = hash;
     for prime in &PRIMES {
         let mut size = 0;
-        if h % prime != 0 {
+        if !h.is_multiple_of(*prime) {
             continue;
         }
-        while h % prime == 0 {
+        while h.is_multiple_of(*prime) {
             size += 1;
             h /= prime;
         }
@@ -259,7 +260,7 @@ fn all_possible_fence_configs(
         if wood > MAX_FENCES {
             continue;
         }
-        let hash = pasture_config_hash(&[arrangement.clone()]);
+        let hash = pasture_config_hash(std::slice::from_ref(arrangement));
         pasture_config_to_min_wood_map
             .entry(hash)
             .and_modify(|e| {
@@ -276,7 +277,7 @@ fn all_possible_fence_configs(
             continue;
         }
 
-        let hash = pasture_config_hash(&[arrangement.clone()]);
+        let hash = pasture_config_hash(std::slice::from_ref(&arrangement));
         let min_wood = pasture_config_to_min_wood_map.get(&hash).unwrap();
 
         if wood > *min_wood {
@@ -289,7 +290,7 @@ fn all_possible_fence_configs(
     let mut all_pastures = Vec::new();
 
     for (p, w) in &single_pastures {
-        if breaks_connectivity(&[p.clone()], room_and_field_spaces) {
+        if breaks_connectivity(std::slice::from_ref(p), room_and_field_spaces) {
             continue;
         }
         all_pastures.push((vec![p.clone()], *w));
@@ -335,41 +336,49 @@ fn all_possible_fence_configs(
     possible_pastures_from_wood
 }
 
+/// Animals each pasture can hold, by pasture index, 0 for pastures that are not fenced
 #[must_use]
-pub fn get_existing_pasture_capacities(farmyard_spaces: &[FarmyardSpace]) -> Vec<usize> {
-    let mut ret = Vec::new();
-
-    // Add capacity for the house pet
-    ret.push(1);
-
+pub fn pasture_capacities(farmyard_spaces: &[FarmyardSpace]) -> [usize; MAX_PASTURES] {
     let mut bare_capacities = [0; MAX_PASTURES];
     let mut stables = [0; MAX_PASTURES];
 
     for space in farmyard_spaces {
-        match *space {
-            FarmyardSpace::FencedPasture(stable, pasture_idx) => {
-                bare_capacities[pasture_idx] += 2;
-                if stable {
-                    stables[pasture_idx] += 1;
-                }
-            }
-            FarmyardSpace::UnfencedStable => {
-                // Each unfenced stable can hold 1 animal
-                ret.push(1);
+        if let FarmyardSpace::FencedPasture(stable, pasture_idx) = *space {
+            bare_capacities[pasture_idx] += 2;
+            if stable {
+                stables[pasture_idx] += 1;
             }
-            _ => (),
         }
     }
 
-    for i in 0..MAX_PASTURES {
-        if bare_capacities[i] > 0 {
-            if stables[i] == 0 {
-                ret.push(bare_capacities[i]);
-            } else {
-                ret.push(bare_capacities[i] * STABLE_MULTIPLIER * stables[i]);
-            }
+    core::array::from_fn(|i| {
+        if stables[i] == 0 {
+            bare_capacities[i]
+        } else {
+            bare_capacities[i] * STABLE_MULTIPLIER * stables[i]
+        }
+    })
+}
+
+#[must_use]
+pub fn get_existing_pasture_capacities(farmyard_spaces: &[FarmyardSpace]) -> Vec<usize> {
+    let mut ret = Vec::new();
+
+    // Add capacity for the house pet
+    ret.push(1);
+
+    for space in farmyard_spaces {
+        if *space == FarmyardSpace::UnfencedStable {
+            // Each unfenced stable can hold 1 animal
+            ret.push(1);
         }
     }
+
+    ret.extend(
+        pasture_capacities(farmyard_spaces)
+            .into_iter()
+            .filter(|capacity| *capacity > 0),
+    );
     ret
 }
 
@@ -390,6 +399,27 @@ pub fn get_existing_pastures(farmyard_spaces: &[FarmyardSpace]) -> Vec<Pasture>
         .collect()
 }
 
+/// Fences needed to enclose the pastures on a farmyard. A fence between two adjacent pastures is shared.
+#[must_use]
+pub fn fences_required(farmyard_spaces: &[FarmyardSpace]) -> usize {
+    let mut ret = 0;
+    for (idx, space) in farmyard_spaces.iter().enumerate() {
+        let FarmyardSpace::FencedPasture(_, pasture_idx) = space else {
+            continue;
+        };
+        for neighbor in NEIGHBOR_SPACES[idx] {
+            match neighbor.map(|n| (n, farmyard_spaces[n])) {
+                Some((_, FarmyardSpace::FencedPasture(_, other_idx)))
+                    if other_idx == *pasture_idx => {}
+                // Count a shared fence only from the lower index side
+                Some((n, FarmyardSpace::FencedPasture(_, _))) if n < idx => (),
+                _ => ret += 1,
+            }
+        }
+    }
+    ret
+}
+
 #[must_use]
 pub fn get_all_pasture_configs(farmyard_spaces: &[FarmyardSpace]) -> Vec<PastureConfig> {
     let mut room_and_field_spaces = [
@@ -442,12 +472,25 @@ pub fn get_all_pasture_configs(farmyard_spaces: &[FarmyardSpace]) -> Vec<Pasture
         }
     }
 
+    // An extension covers every space of the config it extends, comparing the spaces as bits first skips most pairs
+    let space_masks = ret
+        .iter()
+        .map(|config| {
+            config
+                .pastures
+                .iter()
+                .flatten()
+                .fold(0_u32, |mask, idx| mask | 1 << idx)
+        })
+        .collect::<Vec<_>>();
     let mut extensions = Vec::new();
 
-    for pasture_config in &ret {
+    for (pasture_config, mask) in ret.iter().zip(&space_masks) {
         let mut num_extensions = 0;
-        for pasture_config_other in &ret {
-            if is_future_extension(&pasture_config_other.pastures, &pasture_config.pastures) {
+        for (pasture_config_other, other_mask) in ret.iter().zip(&space_masks) {
+            if other_mask & mask == *mask
+                && is_future_extension(&pasture_config_other.pastures, &pasture_config.pastures)
+            {
                 num_extensions += 1;
             }
         }
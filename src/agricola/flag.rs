@@ -10,12 +10,6 @@ pub struct GotEnoughFood;
 pub struct HarvestPaid;
 pub struct HasRoomToGrow;
 pub struct BeforeRoundStart;
-pub struct BakedOnceWithClayOven;
-pub struct BakedOnceWithStoneOven;
-pub struct BakedTwiceWithStoneOven;
-pub struct UsedJoinery;
-pub struct UsedPottery;
-pub struct UsedBasketmakersWorkshop;
 
 impl Flag for WoodHouse {
     fn index(&self) -> usize {
@@ -65,40 +59,4 @@ impl Flag for BeforeRoundStart {
     }
 }
 
-impl Flag for BakedOnceWithClayOven {
-    fn index(&self) -> usize {
-        8
-    }
-}
-
-impl Flag for BakedOnceWithStoneOven {
-    fn index(&self) -> usize {
-        9
-    }
-}
-
-impl Flag for BakedTwiceWithStoneOven {
-    fn index(&self) -> usize {
-        10
-    }
-}
-
-impl Flag for UsedJoinery {
-    fn index(&self) -> usize {
-        11
-    }
-}
-
-impl Flag for UsedPottery {
-    fn index(&self) -> usize {
-        12
-    }
-}
-
-impl Flag for UsedBasketmakersWorkshop {
-    fn index(&self) -> usize {
-        13
-    }
-}
-
-pub const NUM_FLAGS: usize = 14;
+pub const NUM_FLAGS: usize = 8;
@@ -0,0 +1,440 @@
+use super::actions::{Action, RuleError};
+use super::algorithms::PlayerType;
+use super::ruleset::Ruleset;
+use super::state::State;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Version of the game file format, bumped whenever older files can no longer be read
+pub const GAME_FILE_VERSION: u32 = 1;
+
+/// A saved game. The game is rebuilt by replaying `actions` on a new game made from the players, seed and ruleset,
+/// which is the only way a game is resumed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameFile {
+    pub version: u32,
+    pub players: Vec<PlayerType>,
+    pub seed: u64,
+    pub ruleset: Ruleset,
+    pub actions: Vec<Action>,
+    /// The state at the end of `actions`, written for people reading the file such as in bug reports.
+    /// It is never loaded as the state of the game : loading checks that the replay ends in the same state,
+    /// and a file whose snapshot differs is refused.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<serde_json::Value>,
+}
+
+/// Final or current score of a player
+#[derive(Clone, Debug, PartialEq)]
+pub struct Standing {
+    pub player_idx: usize,
+    pub score: f64,
+}
+
+/// A game of Agricola as seen by bots and tools. Chance nodes are resolved by the game itself,
+/// and moves that are the only choice are played automatically unless that is turned off.
+/// Every action is recorded, so moves can be undone and redone by replaying the history.
+#[derive(Clone)]
+pub struct Game {
+    initial: State,
+    state: State,
+    auto_apply_forced: bool,
+    // Every action applied to the initial state, chance outcomes and forced moves included
+    history: Vec<Action>,
+    // Positions in the history of the moves played through `play`
+    moves: Vec<usize>,
+    // Moves undone, the most recent last
+    undone: Vec<Action>,
+}
+
+impl Game {
+    #[must_use]
+    pub fn new(players: &[PlayerType]) -> Option<Self> {
+        State::new(players).map(Self::from_state)
+    }
+
+    #[must_use]
+    pub fn with_seed(players: &[PlayerType], seed: u64) -> Option<Self> {
+        State::with_seed(players, seed).map(Self::from_state)
+    }
+
+    #[must_use]
+    pub fn with_ruleset_and_seed(
+        players: &[PlayerType],
+        ruleset: Ruleset,
+        seed: u64,
+    ) -> Option<Self> {
+        State::with_ruleset_and_seed(players, ruleset, seed).map(Self::from_state)
+    }
+
+    /// Continues from an existing state, forced moves are applied automatically
+    #[must_use]
+    pub fn from_state(state: State) -> Self {
+        let mut game = Self {
+            initial: state.clone(),
+            state,
+            auto_apply_forced: true,
+            history: Vec::new(),
+            moves: Vec::new(),
+            undone: Vec::new(),
+        };
+        game.advance();
+        game
+    }
+
+    /// Whether moves that are the only choice are played without asking. On by default.
+    pub fn set_auto_apply_forced(&mut self, auto_apply_forced: bool) {
+        self.auto_apply_forced = auto_apply_forced;
+        self.advance();
+    }
+
+    #[must_use]
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    /// The moves the current player can choose from, empty once the game is over
+    #[must_use]
+    pub fn legal_moves(&self) -> Vec<Action> {
+        Action::next_choices(&self.state)
+            .into_iter()
+            .map(|(action, _)| action)
+            .collect()
+    }
+
+    /// Plays a move for the current player, then any chance outcomes and forced moves that follow
+    /// # Errors
+    /// If the game is over or the move is not legal
+    pub fn play(&mut self, action: &Action) -> Result<(), RuleError> {
+        self.play_move(action)?;
+        self.undone.clear();
+        Ok(())
+    }
+
+    fn play_move(&mut self, action: &Action) -> Result<(), RuleError> {
+        self.state.try_apply(action)?;
+        self.moves.push(self.history.len());
+        self.history.push(action.clone());
+        self.advance();
+        Ok(())
+    }
+
+    /// The moves played so far in notation, separated by spaces
+    #[must_use]
+    pub fn record(&self) -> String {
+        self.moves
+            .iter()
+            .map(|&idx| self.history[idx].notation())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Plays moves written in notation and separated by whitespace, as written by `record`
+    /// # Errors
+    /// If a move is malformed or not legal, the moves before it stay played
+    pub fn play_record(&mut self, record: &str) -> Result<(), String> {
+        for (i, notation) in record.split_whitespace().enumerate() {
+            let action = Action::from_notation(notation, &self.state)
+                .map_err(|e| format!("Move {} : {e}", i + 1))?;
+            self.play(&action).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Every action applied since the start, including chance outcomes and forced moves
+    #[must_use]
+    pub fn history(&self) -> &[Action] {
+        &self.history
+    }
+
+    #[must_use]
+    pub fn can_undo(&self) -> bool {
+        !self.moves.is_empty()
+    }
+
+    #[must_use]
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Takes back the last move played, returns false if there is none
+    pub fn undo(&mut self) -> bool {
+        let Some(move_idx) = self.moves.pop() else {
+            return false;
+        };
+        self.undone.push(self.history[move_idx].clone());
+        self.state = self.state_after(move_idx);
+        self.history.truncate(move_idx);
+        true
+    }
+
+    /// Plays the last undone move again, returns false if there is none
+    pub fn redo(&mut self) -> bool {
+        let Some(action) = self.undone.pop() else {
+            return false;
+        };
+        self.play_move(&action)
+            .expect("An undone move is legal in the state it was undone to");
+        true
+    }
+
+    /// The game as a file, with a snapshot of the current state to verify the replay against if asked for
+    /// # Errors
+    /// If the game was not played from its first move, or the snapshot cannot be serialized
+    pub fn to_file(&self, with_snapshot: bool) -> Result<GameFile, String> {
+        if !matches!(self.initial.last_action, Action::StartGame) {
+            return Err(String::from(
+                "Only games played from their first move can be saved",
+            ));
+        }
+        let snapshot = if with_snapshot {
+            Some(serde_json::to_value(&self.state).map_err(|e| e.to_string())?)
+        } else {
+            None
+        };
+        Ok(GameFile {
+            version: GAME_FILE_VERSION,
+            players: (0..self.state.num_players)
+                .map(|i| self.state.player_type(i))
+                .collect(),
+            seed: self.state.seed(),
+            ruleset: self.state.ruleset().clone(),
+            actions: self.history.clone(),
+            snapshot,
+        })
+    }
+
+    /// Replays a saved game, the moves in it can be undone
+    /// # Errors
+    /// If the file is from another version, or its actions or snapshot do not match its seed and rules
+    pub fn from_file(file: &GameFile) -> Result<Self, String> {
+        if file.version != GAME_FILE_VERSION {
+            return Err(format!(
+                "Game file version {} is not supported, expected {GAME_FILE_VERSION}",
+                file.version
+            ));
+        }
+        file.ruleset.validate()?;
+        let mut game = Self::with_ruleset_and_seed(&file.players, file.ruleset.clone(), file.seed)
+            .ok_or("A game needs at least one player")?;
+
+        while game.history.len() < file.actions.len() {
+            if game.history[..] != file.actions[..game.history.len()] {
+                break;
+            }
+            let action = &file.actions[game.history.len()];
+            game.play(action)
+                .map_err(|e| format!("Action {} : {e}", game.history.len()))?;
+        }
+        if game.history.len() < file.actions.len()
+            || game.history[..file.actions.len()] != file.actions[..]
+        {
+            return Err(String::from(
+                "The actions of the game file do not match its seed",
+            ));
+        }
+
+        if let Some(snapshot) = &file.snapshot {
+            if serde_json::to_value(&game.state).map_err(|e| e.to_string())? != *snapshot {
+                return Err(String::from(
+                    "The replayed game does not match the snapshot of the game file",
+                ));
+            }
+        }
+        Ok(game)
+    }
+
+    /// # Errors
+    /// If the game cannot be saved or serialized
+    pub fn to_json(&self, with_snapshot: bool) -> Result<String, String> {
+        serde_json::to_string_pretty(&self.to_file(with_snapshot)?).map_err(|e| e.to_string())
+    }
+
+    /// # Errors
+    /// If the JSON is malformed or does not hold a valid game
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let file: GameFile = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        Self::from_file(&file)
+    }
+
+    /// # Errors
+    /// If the game cannot be saved or the file cannot be written
+    pub fn save<P: AsRef<Path>>(&self, path: P, with_snapshot: bool) -> Result<(), String> {
+        fs::write(path.as_ref(), self.to_json(with_snapshot)?)
+            .map_err(|e| format!("{}: {e}", path.as_ref().display()))
+    }
+
+    /// # Errors
+    /// If the file cannot be read or does not hold a valid game
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let json = fs::read_to_string(path.as_ref())
+            .map_err(|e| format!("{}: {e}", path.as_ref().display()))?;
+        Self::from_json(&json)
+    }
+
+    /// The state after the first `num_actions` actions of the history, to walk back through a game
+    /// # Panics
+    /// If `num_actions` is longer than the history
+    #[must_use]
+    pub fn state_after(&self, num_actions: usize) -> State {
+        let mut state = self.initial.clone();
+        for action in &self.history[..num_actions] {
+            // Draw chance outcomes again so that the random number generator stays in step
+            if state.is_chance_node() {
+                let _ = state.sample_chance();
+            }
+            action.apply_choice(&mut state);
+        }
+        state
+    }
+
+    /// The player to move, `None` once the game is over
+    #[must_use]
+    pub fn current_player(&self) -> Option<usize> {
+        if self.is_over() {
+            None
+        } else {
+            Some(self.state.current_player_idx)
+        }
+    }
+
+    #[must_use]
+    pub fn is_over(&self) -> bool {
+        Action::next_choices(&self.state).is_empty()
+    }
+
+    /// Scores of all players, best first
+    #[must_use]
+    pub fn standings(&self) -> Vec<Standing> {
+        let scores = self.state.scores();
+        let mut ret: Vec<Standing> = (0..self.state.num_players)
+            .map(|player_idx| Standing {
+                player_idx,
+                score: scores[player_idx],
+            })
+            .collect();
+        ret.sort_by(|a, b| b.score.total_cmp(&a.score));
+        ret
+    }
+
+    // Resolve chance nodes, and forced moves if asked to, until a player has a decision to make
+    fn advance(&mut self) {
+        loop {
+            let action = if self.state.is_chance_node() {
+                self.state.sample_chance()
+            } else {
+                let mut choices = Action::next_choices(&self.state);
+                if !self.auto_apply_forced || choices.len() != 1 {
+                    break;
+                }
+                choices.pop().unwrap().0
+            };
+            action.apply_choice(&mut self.state);
+            self.history.push(action);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_play_until_over() {
+        let mut game = Game::with_seed(&[PlayerType::Human; 2], 17).unwrap();
+        assert!(!game.state().is_chance_node());
+        assert!(game.legal_moves().len() > 1);
+
+        while let Some(action) = game.legal_moves().first().cloned() {
+            assert!(game.current_player().is_some());
+            game.play(&action).unwrap();
+            assert!(!game.state().is_chance_node());
+        }
+
+        assert!(game.is_over());
+        assert_eq!(game.current_player(), None);
+        assert_eq!(game.play(&Action::EndTurn), Err(RuleError::GameOver));
+        let standings = game.standings();
+        assert_eq!(standings.len(), 2);
+        assert!(standings[0].score >= standings[1].score);
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut game = Game::with_seed(&[PlayerType::Human; 2], 19).unwrap();
+        assert!(!game.can_undo());
+        let start = game.state().get_hash();
+
+        let mut hashes = vec![start];
+        for _ in 0..20 {
+            let action = game.legal_moves().last().cloned().unwrap();
+            game.play(&action).unwrap();
+            hashes.push(game.state().get_hash());
+        }
+
+        let end = game.state().get_hash();
+        while game.undo() {
+            hashes.pop();
+            assert_eq!(game.state().get_hash(), *hashes.last().unwrap());
+        }
+        assert_eq!(game.state().get_hash(), start);
+
+        // Chance outcomes come out the same when the moves are played again
+        while game.redo() {}
+        assert_eq!(game.state().get_hash(), end);
+        assert_eq!(game.moves.len(), 20);
+        assert!(game.state().current_round > 1);
+
+        // Playing a new move drops the moves that could be redone
+        game.undo();
+        let action = game.legal_moves().first().cloned().unwrap();
+        game.play(&action).unwrap();
+        assert!(!game.can_redo());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let mut game = Game::with_seed(&[PlayerType::Human, PlayerType::MctsAI], 23).unwrap();
+        for _ in 0..25 {
+            let action = game.legal_moves().last().cloned().unwrap();
+            game.play(&action).unwrap();
+        }
+
+        let json = game.to_json(true).unwrap();
+        let loaded = Game::from_json(&json).unwrap();
+        assert_eq!(loaded.state().get_hash(), game.state().get_hash());
+        assert_eq!(loaded.history(), game.history());
+        assert_eq!(loaded.state().player_type(1), PlayerType::MctsAI);
+
+        // The snapshot is only compared with the replay, a changed one refuses the file
+        let mut file = game.to_file(true).unwrap();
+        file.snapshot.as_mut().unwrap()["current_round"] = serde_json::json!(99);
+        assert!(Game::from_file(&file).is_err());
+
+        // Tampering with the actions is caught by the replay
+        let mut file = game.to_file(false).unwrap();
+        file.actions.truncate(file.actions.len() - 1);
+        file.actions.push(Action::EndGame);
+        assert!(Game::from_file(&file).is_err());
+
+        file.version += 1;
+        assert!(Game::from_file(&file).is_err());
+    }
+
+    #[test]
+    fn test_record_replay() {
+        let mut game = Game::with_seed(&[PlayerType::Human; 3], 37).unwrap();
+        for _ in 0..30 {
+            let action = game.legal_moves().first().cloned().unwrap();
+            game.play(&action).unwrap();
+        }
+
+        let record = game.record();
+        assert_eq!(record.split(' ').count(), 30);
+        let mut replay = Game::with_seed(&[PlayerType::Human; 3], 37).unwrap();
+        replay.play_record(&record).unwrap();
+        assert_eq!(replay.state().get_hash(), game.state().get_hash());
+        assert!(replay.play_record("U0 XYZ").is_err());
+    }
+}
@@ -1,12 +1,23 @@
+pub mod action_index;
 pub mod action_space;
 pub mod actions;
 pub mod algorithms;
 pub mod card;
 pub mod display;
+pub mod env;
+pub mod external;
 pub mod farm;
+pub mod features;
+pub mod feeding;
 pub mod fencing;
 pub mod flag;
+pub mod game;
+pub mod notation;
+pub mod observation;
 pub mod paranoid_ai;
+pub mod position;
 pub mod quantity;
+pub mod ruleset;
 pub mod scoring;
 pub mod state;
+pub mod validation;
@@ -0,0 +1,487 @@
+use super::actions::{
+    Action, CalledFromCultivation, CalledFromFarmRedevelopment, CalledFromGrainUtilization,
+    CalledFromHouseRedevelopment, ConversionStage, ReturnFireplace, WithRoom,
+};
+use super::card::Renovation;
+use super::farm::{Seed, NUM_FARMYARD_SPACES};
+use super::feeding::FeedingPlan;
+use super::fencing::{pasture_config_hash, Pasture, PastureConfig, MAX_PASTURES};
+use super::quantity::{new_res, ResourceExchange, Resources, NUM_RESOURCES};
+use super::state::State;
+use std::str::FromStr;
+
+// Wood, Clay, Stone, Food, Reed, Grain, Vegetable, Sheep, Boar, Cattle
+pub const RESOURCE_LETTERS: [&str; NUM_RESOURCES] =
+    ["W", "C", "S", "F", "R", "G", "V", "Sh", "Bo", "Ca"];
+
+// The codes every move notation starts with
+const CODES: [&str; 23] = [
+    "SR", "PW", "BR", "BS", "BC", "ET", "EG", "BM", "BB", "SW", "RN", "GF", "FN", "PL", "CV", "PH",
+    "PF", "SG", "OC", "CH", "U", "R", "H",
+];
+
+fn exchange_notation(res_ex: &ResourceExchange, times: usize) -> String {
+    format!(
+        "{}{}>{}{}",
+        res_ex.num_from * times,
+        RESOURCE_LETTERS[res_ex.from],
+        res_ex.num_to * times,
+        RESOURCE_LETTERS[res_ex.to]
+    )
+}
+
+fn joined<T>(items: &[T], sep: &str, f: impl Fn(&T) -> String) -> String {
+    items.iter().map(f).collect::<Vec<_>>().join(sep)
+}
+
+pub(crate) fn parse_num<T: FromStr>(s: &str) -> Result<T, String> {
+    s.parse().map_err(|_| format!("'{s}' is not a number"))
+}
+
+fn parse_resource(s: &str) -> Result<usize, String> {
+    RESOURCE_LETTERS
+        .iter()
+        .position(|l| *l == s)
+        .ok_or_else(|| format!("Unknown resource '{s}'"))
+}
+
+// Goods written as counts followed by resource letters, e.g. 2W1C for 2 wood and 1 clay
+pub(crate) fn format_goods(goods: &Resources) -> String {
+    goods
+        .iter()
+        .enumerate()
+        .filter(|(_, n)| **n > 0)
+        .map(|(i, n)| format!("{n}{}", RESOURCE_LETTERS[i]))
+        .collect()
+}
+
+pub(crate) fn parse_goods(s: &str) -> Result<Resources, String> {
+    let mut ret = new_res();
+    let mut rest = s;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let n: usize = parse_num(&rest[..digits])?;
+        rest = &rest[digits..];
+        let letters = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        ret[parse_resource(&rest[..letters])?] += n;
+        rest = &rest[letters..];
+    }
+    Ok(ret)
+}
+
+// An exchange used `times` times, as written by `exchange_notation`
+fn parse_exchange(s: &str, times: usize) -> Result<ResourceExchange, String> {
+    let amount = |part: &str| -> Result<(usize, usize), String> {
+        let digits = part
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(part.len());
+        let n: usize = parse_num(&part[..digits])?;
+        if times == 0 || !n.is_multiple_of(times) {
+            return Err(format!("'{s}' is not used {times} times"));
+        }
+        Ok((n / times, parse_resource(&part[digits..])?))
+    };
+    let (from, to) = s
+        .split_once('>')
+        .ok_or_else(|| format!("Exchange '{s}' should be goods>goods"))?;
+    let (num_from, from) = amount(from)?;
+    let (num_to, to) = amount(to)?;
+    Ok(ResourceExchange {
+        from,
+        to,
+        num_from,
+        num_to,
+    })
+}
+
+fn bit(b: bool) -> char {
+    if b {
+        '1'
+    } else {
+        '0'
+    }
+}
+
+fn parse_bits<const N: usize>(s: &str) -> Result<[bool; N], String> {
+    let bits = s
+        .chars()
+        .map(|c| match c {
+            '0' => Ok(false),
+            '1' => Ok(true),
+            _ => Err(format!("'{s}' should only have 0 and 1")),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    bits.try_into()
+        .map_err(|_| format!("'{s}' should have {N} digits"))
+}
+
+fn parse_list<T>(
+    s: &str,
+    sep: char,
+    f: impl Fn(&str) -> Result<T, String>,
+) -> Result<Vec<T>, String> {
+    s.split(sep).map(f).collect()
+}
+
+impl Action {
+    /// Compact notation of the action, a single token without spaces so a game can be written as a line of moves.
+    /// It holds everything a player decides, flags recording where a move was reached from are recovered from the position.
+    /// - `U3` use action space 3, `R21` reveal round card 21
+    /// - `BR6` build a room on farmyard space 6, `BS14` a stable, `PL0` plow a field
+    /// - `BC5` build major improvement 5, `BC2r` returning a fireplace, `OC10` play occupation 10
+    /// - `BB5x1+2x3` bake 1 grain on improvement 5 and 3 on improvement 2
+    /// - `SW0G+1V` sow grain on field 0 and a vegetable on field 1
+    /// - `FN13.14/8.9` fence the pastures {13, 14} and {8, 9}
+    /// - `RNC` renovate to clay, `CV1G>1F` convert 1 grain to 1 food, `CHV` take a vegetable with Childless
+    /// - `PF2G>2F+b1` pay food converting 2 grain and beg for 1 food
+    /// - `SG`, `SR`, `PW`, `ET`, `GF`, `BM`, `H`, `PH`, `EG` for the moves without choices
+    #[must_use]
+    pub fn notation(&self) -> String {
+        match self {
+            Self::UseSpace(idx) => format!("U{idx}"),
+            Self::StartRound => String::from("SR"),
+            Self::RevealRoundCard(idx) => format!("R{idx}"),
+            Self::PlaceWorker => String::from("PW"),
+            Self::BuildRoom(idx) => format!("BR{idx}"),
+            Self::BuildStable(idx) => format!("BS{idx}"),
+            Self::BuildCard(idx, return_fireplace) => {
+                format!("BC{idx}{}", if return_fireplace.0 { "r" } else { "" })
+            }
+            Self::Harvest => String::from("H"),
+            Self::EndTurn => String::from("ET"),
+            Self::EndGame => String::from("EG"),
+            Self::BuildMajor => String::from("BM"),
+            Self::BakeBread(_, allocation) => format!(
+                "BB{}",
+                joined(allocation, "+", |(major_idx, grain)| format!(
+                    "{major_idx}x{grain}"
+                ))
+            ),
+            Self::Sow(_, plan) => format!(
+                "SW{}",
+                joined(plan, "+", |(idx, seed)| format!(
+                    "{idx}{}",
+                    match seed {
+                        Seed::Grain => "G",
+                        Seed::Vegetable => "V",
+                    }
+                ))
+            ),
+            Self::Renovate(_, _, renovation) => {
+                format!("RN{}", RESOURCE_LETTERS[renovation.target])
+            }
+            Self::GrowFamily(_) => String::from("GF"),
+            Self::Fence(pasture_config) => format!(
+                "FN{}",
+                joined(&pasture_config.pastures, "/", |pasture| joined(
+                    pasture,
+                    ".",
+                    ToString::to_string
+                ))
+            ),
+            Self::Plow(_, idx) => format!("PL{idx}"),
+            Self::Convert(res_ex, ConversionStage::BeforePlayOccupation(_)) => {
+                format!("CV{}", exchange_notation(res_ex, 1))
+            }
+            Self::PreHarvest => String::from("PH"),
+            Self::PayFoodOrBeg(plan) => {
+                let mut parts: Vec<String> = plan
+                    .exchanges
+                    .iter()
+                    .map(|(res_ex, times)| exchange_notation(res_ex, *times))
+                    .collect();
+                if plan.begging_tokens > 0 {
+                    parts.push(format!("b{}", plan.begging_tokens));
+                }
+                format!("PF{}", parts.join("+"))
+            }
+            Self::StartGame => String::from("SG"),
+            Self::PlayOccupation(occ, _) => format!("OC{occ}"),
+            Self::GetResourceFromChildless(res) => format!("CH{}", RESOURCE_LETTERS[*res]),
+        }
+    }
+
+    /// Reads a move written with `notation`, as one of the choices in `state`
+    /// # Errors
+    /// If the notation is malformed or is not a legal move in `state`
+    pub fn from_notation(notation: &str, state: &State) -> Result<Self, String> {
+        let notation = notation.trim();
+        let is_known = CODES.iter().any(|code| {
+            notation.strip_prefix(code).is_some_and(|rest| {
+                rest.chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "+>./".contains(c))
+            })
+        });
+        if !is_known {
+            return Err(format!("'{notation}' is not a move"));
+        }
+        Self::next_choices(state)
+            .into_iter()
+            .map(|(action, _)| action)
+            .find(|action| action.notation() == notation)
+            .ok_or_else(|| format!("'{notation}' is not a legal move here"))
+    }
+
+    /// The notation followed by what it leaves out, after a `~`, so the move reads back without a position :
+    /// the flags recording where the move was reached from, the cost of a renovation, the wood and extensions of a fencing,
+    /// the food paid for an occupation and the times each exchange is used to pay food. The same as `notation` for moves
+    /// without such fields.
+    /// - `SW0G~10` sow from Grain Utilization before baking, `RNC~015W1R` renovate to clay from Farm Redevelopment for 5 wood and 1 reed
+    /// - `FN13.14~7.2` fence with 7 wood in total, `OC10~1` play occupation 10 for 1 food, `PF2G>2F~2` convert 1 grain twice
+    #[must_use]
+    pub fn full_notation(&self) -> String {
+        let hidden = match self {
+            Self::BakeBread(from, _) | Self::Sow(from, _) => {
+                format!("{}{}", bit(from.0), bit(from.1))
+            }
+            Self::Renovate(house, farm, renovation) => format!(
+                "{}{}{}",
+                bit(house.0),
+                bit(farm.0),
+                format_goods(&renovation.cost)
+            ),
+            Self::GrowFamily(with_room) => bit(with_room.0).to_string(),
+            Self::Fence(config) => format!("{}.{}", config.wood, config.extensions),
+            Self::Plow(from, _) => bit(from.0).to_string(),
+            Self::Convert(_, ConversionStage::BeforePlayOccupation(before)) => {
+                bit(*before).to_string()
+            }
+            Self::PlayOccupation(_, food) => food.to_string(),
+            Self::PayFoodOrBeg(plan) if !plan.exchanges.is_empty() => {
+                joined(&plan.exchanges, ".", |(_, times)| times.to_string())
+            }
+            _ => return self.notation(),
+        };
+        format!("{}~{hidden}", self.notation())
+    }
+
+    /// Reads a move written with `full_notation`, without a position
+    /// # Errors
+    /// If the notation is malformed or misses fields the move needs
+    pub fn from_full_notation(notation: &str) -> Result<Self, String> {
+        let notation = notation.trim();
+        // Every notation is ASCII, which lets the fields be split at byte positions
+        if !notation.is_ascii() {
+            return Err(format!("'{notation}' is not a move"));
+        }
+        let (shown, hidden) = match notation.split_once('~') {
+            Some((shown, hidden)) => (shown, Some(hidden)),
+            None => (notation, None),
+        };
+        // Two letter codes first, so RN is not read as R
+        let code = CODES
+            .iter()
+            .filter(|code| shown.starts_with(*code))
+            .max_by_key(|code| code.len())
+            .ok_or_else(|| format!("'{notation}' is not a move"))?;
+        let rest = &shown[code.len()..];
+        let hidden = || hidden.ok_or_else(|| format!("'{notation}' misses the fields after '~'"));
+        let flags = |s: &str| -> Result<(bool, bool), String> {
+            let [a, b] = parse_bits::<2>(s)?;
+            Ok((a, b))
+        };
+
+        let action = match *code {
+            "U" => Self::UseSpace(parse_num(rest)?),
+            "R" => Self::RevealRoundCard(parse_num(rest)?),
+            "BR" => Self::BuildRoom(parse_num(rest)?),
+            "BS" => Self::BuildStable(parse_num(rest)?),
+            "BC" => match rest.strip_suffix('r') {
+                Some(idx) => Self::BuildCard(parse_num(idx)?, ReturnFireplace(true)),
+                None => Self::BuildCard(parse_num(rest)?, ReturnFireplace(false)),
+            },
+            "BB" => {
+                let (a, b) = flags(hidden()?)?;
+                let allocation = parse_list(rest, '+', |item| {
+                    let (major_idx, grain) = item
+                        .split_once('x')
+                        .ok_or_else(|| format!("'{item}' should be improvement x grain"))?;
+                    Ok((parse_num(major_idx)?, parse_num(grain)?))
+                })?;
+                Self::BakeBread(CalledFromGrainUtilization(a, b), allocation)
+            }
+            "SW" => {
+                let (a, b) = flags(hidden()?)?;
+                let plan = parse_list(rest, '+', |item| {
+                    let seed = match item.chars().last() {
+                        Some('G') => Seed::Grain,
+                        Some('V') => Seed::Vegetable,
+                        _ => return Err(format!("'{item}' should end in G or V")),
+                    };
+                    Ok((parse_num(&item[..item.len() - 1])?, seed))
+                })?;
+                Self::Sow(CalledFromGrainUtilization(a, b), plan)
+            }
+            "RN" => {
+                let hidden = hidden()?;
+                let (bits, cost) = hidden.split_at(hidden.len().min(2));
+                let (house, farm) = flags(bits)?;
+                Self::Renovate(
+                    CalledFromHouseRedevelopment(house),
+                    CalledFromFarmRedevelopment(farm),
+                    Renovation {
+                        target: parse_resource(rest)?,
+                        cost: parse_goods(cost)?,
+                    },
+                )
+            }
+            "GF" if rest.is_empty() => {
+                let [with_room] = parse_bits::<1>(hidden()?)?;
+                Self::GrowFamily(WithRoom(with_room))
+            }
+            "FN" => {
+                let (wood, extensions) = hidden()?
+                    .split_once('.')
+                    .ok_or_else(|| format!("'{notation}' should end in ~wood.extensions"))?;
+                let pastures: Vec<Pasture> =
+                    parse_list(rest, '/', |pasture| parse_list(pasture, '.', parse_num))?;
+                let mut spaces: Vec<usize> = pastures.concat();
+                spaces.sort_unstable();
+                spaces.dedup();
+                if pastures.len() > MAX_PASTURES
+                    || pastures.iter().any(Vec::is_empty)
+                    || spaces.len() != pastures.iter().map(Vec::len).sum::<usize>()
+                    || spaces.iter().any(|idx| *idx >= NUM_FARMYARD_SPACES)
+                {
+                    return Err(format!(
+                        "'{notation}' should fence at most {MAX_PASTURES} pastures of distinct farmyard spaces"
+                    ));
+                }
+                Self::Fence(PastureConfig {
+                    hash: pasture_config_hash(&pastures),
+                    pastures,
+                    wood: parse_num(wood)?,
+                    extensions: parse_num(extensions)?,
+                })
+            }
+            "PL" => {
+                let [from] = parse_bits::<1>(hidden()?)?;
+                Self::Plow(CalledFromCultivation(from), parse_num(rest)?)
+            }
+            "CV" => {
+                let [before] = parse_bits::<1>(hidden()?)?;
+                Self::Convert(
+                    parse_exchange(rest, 1)?,
+                    ConversionStage::BeforePlayOccupation(before),
+                )
+            }
+            "PF" => {
+                let mut parts: Vec<&str> = if rest.is_empty() {
+                    Vec::new()
+                } else {
+                    rest.split('+').collect()
+                };
+                let begging_tokens = match parts.last().and_then(|p| p.strip_prefix('b')) {
+                    Some(n) => {
+                        let n = parse_num(n)?;
+                        parts.pop();
+                        n
+                    }
+                    None => 0,
+                };
+                let times: Vec<usize> = if parts.is_empty() {
+                    Vec::new()
+                } else {
+                    parse_list(hidden()?, '.', parse_num)?
+                };
+                if times.len() != parts.len() {
+                    return Err(format!(
+                        "'{notation}' should give the times of each exchange"
+                    ));
+                }
+                let exchanges = parts
+                    .iter()
+                    .zip(times)
+                    .map(|(part, times)| Ok((parse_exchange(part, times)?, times)))
+                    .collect::<Result<_, String>>()?;
+                Self::PayFoodOrBeg(FeedingPlan {
+                    exchanges,
+                    begging_tokens,
+                })
+            }
+            "OC" => Self::PlayOccupation(parse_num(rest)?, parse_num(hidden()?)?),
+            "CH" => Self::GetResourceFromChildless(parse_resource(rest)?),
+            "SR" if rest.is_empty() => Self::StartRound,
+            "PW" if rest.is_empty() => Self::PlaceWorker,
+            "ET" if rest.is_empty() => Self::EndTurn,
+            "EG" if rest.is_empty() => Self::EndGame,
+            "BM" if rest.is_empty() => Self::BuildMajor,
+            "PH" if rest.is_empty() => Self::PreHarvest,
+            "SG" if rest.is_empty() => Self::StartGame,
+            "H" if rest.is_empty() => Self::Harvest,
+            _ => return Err(format!("'{notation}' is not a move")),
+        };
+        // Anything the notation of the move would not write, such as leading zeros, is refused
+        if action.full_notation() == notation {
+            Ok(action)
+        } else {
+            Err(format!("'{notation}' is not a move"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agricola::algorithms::PlayerType;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_notation_round_trip() {
+        let mut rng = ChaCha8Rng::seed_from_u64(29);
+        for seed in 0..2 {
+            let mut state = State::with_seed(&[PlayerType::Human; 3], seed).unwrap();
+            loop {
+                let choices = Action::next_choices(&state);
+                if choices.is_empty() {
+                    break;
+                }
+                // Every legal move has its own notation, and reads back as itself
+                let notations: HashSet<String> = choices
+                    .iter()
+                    .map(|(action, _)| action.notation())
+                    .collect();
+                assert_eq!(notations.len(), choices.len());
+                for (action, _) in &choices {
+                    assert_eq!(
+                        Action::from_notation(&action.notation(), &state).as_ref(),
+                        Ok(action)
+                    );
+                    assert_eq!(
+                        Action::from_full_notation(&action.full_notation()).as_ref(),
+                        Ok(action)
+                    );
+                }
+
+                let idx = rng.gen_range(0..choices.len());
+                choices[idx].0.apply_choice(&mut state);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bad_notation() {
+        let state = State::with_seed(&[PlayerType::Human], 31).unwrap();
+        assert!(Action::from_notation("SR", &state).is_ok());
+        assert!(Action::from_notation("ET", &state).is_err());
+        assert!(Action::from_notation("Grove", &state).is_err());
+        assert!(Action::from_notation("U 3", &state).is_err());
+
+        assert_eq!(Action::from_full_notation("U3"), Ok(Action::UseSpace(3)));
+        assert!(Action::from_full_notation("PL3").is_err());
+        assert!(Action::from_full_notation("U03").is_err());
+        assert!(Action::from_full_notation("ETX").is_err());
+        assert!(Action::from_full_notation("RNC~0é").is_err());
+        let big_pasture = joined(&(0..70).collect::<Vec<usize>>(), ".", ToString::to_string);
+        assert!(Action::from_full_notation(&format!("FN{big_pasture}~1.0")).is_err());
+        assert!(Action::from_full_notation("FN0/1/2/3/4~1.0").is_err());
+        assert!(Action::from_full_notation("FN0.0~1.0").is_err());
+        assert!(Action::from_full_notation("FN15~1.0").is_err());
+    }
+}
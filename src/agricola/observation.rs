@@ -0,0 +1,152 @@
+use super::actions::Action;
+use super::algorithms::PlayerType;
+use super::card::NUM_CARDS;
+use super::farm::Farm;
+use super::flag::NUM_FLAGS;
+use super::quantity::{Resources, NUM_QUANTITIES};
+use super::ruleset::Ruleset;
+use super::state::{ScheduledGoods, State};
+use serde::{Deserialize, Serialize};
+
+/// What everyone sees of a player. Cards are played face up, majors and occupations come from a shared pool.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlayerObservation {
+    pub player_type: PlayerType,
+    pub quantities: [usize; NUM_QUANTITIES],
+    pub flags: [bool; NUM_FLAGS],
+    pub cards: [bool; NUM_CARDS],
+    pub farm: Farm,
+}
+
+/// The game as one player can legally know it. The round cards still to come are only known as a set,
+/// and the seed and random number generator deciding them are left out.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Observation {
+    pub ruleset: Ruleset,
+    pub player_idx: usize,
+    pub current_round: usize,
+    pub harvest_done: bool,
+    pub current_player_idx: usize,
+    pub starting_player_idx: usize,
+    pub people_placed_this_round: usize,
+    pub revealed_round_cards: Vec<usize>,
+    // Sorted, the order they will be revealed in is hidden
+    pub unrevealed_round_cards: Vec<usize>,
+    pub occupied: Vec<Option<usize>>,
+    pub accumulated_resources: Vec<Resources>,
+    pub scheduled_goods: Vec<ScheduledGoods>,
+    pub players: Vec<PlayerObservation>,
+    pub last_action: Action,
+}
+
+impl State {
+    /// The view of the game of `player_idx`, the only input UIs, network clients and fair AIs should need
+    #[must_use]
+    pub fn observation(&self, player_idx: usize) -> Observation {
+        let open_spaces = self.ruleset().num_open_spaces();
+        let revealed_end = open_spaces + self.current_round;
+        let mut unrevealed_round_cards = self.action_spaces[revealed_end..].to_vec();
+        unrevealed_round_cards.sort_unstable();
+        Observation {
+            ruleset: self.ruleset().clone(),
+            player_idx,
+            current_round: self.current_round,
+            harvest_done: self.harvest_done,
+            current_player_idx: self.current_player_idx,
+            starting_player_idx: self.starting_player_idx,
+            people_placed_this_round: self.people_placed_this_round,
+            revealed_round_cards: self.action_spaces[open_spaces..revealed_end].to_vec(),
+            unrevealed_round_cards,
+            occupied: self.occupied.to_vec(),
+            accumulated_resources: self.accumulated_resources.to_vec(),
+            scheduled_goods: self.scheduled_goods.clone(),
+            players: (0..self.num_players)
+                .map(|idx| PlayerObservation {
+                    player_type: self.player_type(idx),
+                    quantities: *self.player_quantities(idx),
+                    flags: *self.player_flags(idx),
+                    cards: *self.player_cards(idx),
+                    farm: self.player_farm(idx).clone(),
+                })
+                .collect(),
+            last_action: self.last_action.clone(),
+        }
+    }
+}
+
+impl Observation {
+    /// A state agreeing with everything observed, with the hidden future drawn from `seed`.
+    /// Searching from such states keeps an AI from using what its player cannot know.
+    /// # Panics
+    /// If the observation has no players, or round cards that cannot have been revealed
+    #[must_use]
+    pub fn determinize(&self, seed: u64) -> State {
+        let player_types: Vec<PlayerType> = self.players.iter().map(|p| p.player_type).collect();
+        let mut state =
+            State::with_ruleset_and_seed(&player_types, self.ruleset.clone(), seed).unwrap();
+        state.current_round = self.current_round;
+        state.harvest_done = self.harvest_done;
+        state.current_player_idx = self.current_player_idx;
+        state.starting_player_idx = self.starting_player_idx;
+        state.people_placed_this_round = self.people_placed_this_round;
+        for (i, card_idx) in self.revealed_round_cards.iter().enumerate() {
+            state.reveal_round_card(i + 1, *card_idx);
+        }
+        state.occupied.copy_from_slice(&self.occupied);
+        state
+            .accumulated_resources
+            .copy_from_slice(&self.accumulated_resources);
+        state.scheduled_goods.clone_from(&self.scheduled_goods);
+        for (idx, player) in self.players.iter().enumerate() {
+            *state.player_quantities_mut(idx) = player.quantities;
+            *state.player_flags_mut(idx) = player.flags;
+            *state.player_cards_mut(idx) = player.cards;
+            *state.player_farm_mut(idx) = player.farm.clone();
+            state.restore_fence_options(idx);
+        }
+        state.last_action = self.last_action.clone();
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_observation_hides_the_future() {
+        let mut state = State::with_seed(&[PlayerType::Human, PlayerType::MctsAI], 53).unwrap();
+        state.play_random(Some(60));
+        let observation = state.observation(1);
+        assert_eq!(observation.revealed_round_cards.len(), state.current_round);
+        assert!(observation
+            .unrevealed_round_cards
+            .windows(2)
+            .all(|w| w[0] < w[1]));
+
+        // The same position reached with another seed looks the same
+        let determinized = observation.determinize(7);
+        assert_ne!(determinized.seed(), state.seed());
+        assert_eq!(determinized.observation(1), observation);
+        assert!(determinized.validate().is_empty());
+        assert_eq!(
+            Action::next_choices(&determinized).len(),
+            Action::next_choices(&state).len()
+        );
+    }
+
+    #[test]
+    fn test_determinize_keeps_the_ruleset() {
+        let ruleset = Ruleset {
+            harvest_rounds: vec![3, 6, 9, 12, 14],
+            max_family_members: 4,
+            ..Default::default()
+        };
+        let mut state =
+            State::with_ruleset_and_seed(&[PlayerType::Human; 2], ruleset.clone(), 59).unwrap();
+        state.play_random(Some(40));
+        let determinized = state.observation(0).determinize(3);
+        assert_eq!(*determinized.ruleset(), ruleset);
+        assert_eq!(determinized.observation(0), state.observation(0));
+    }
+}
@@ -26,6 +26,26 @@ pub fn search(
         return search(&state_tmp, player_idx, depth, alpha, beta, num_seen);
     }
 
+    // Expected value over the chance outcomes
+    if state.is_chance_node() {
+        let mut expected = 0.0;
+        for (action, probability) in &actions {
+            let mut state_tmp = state.clone();
+            action.apply_choice(&mut state_tmp);
+            *num_seen += 1;
+            let v = search(
+                &state_tmp,
+                player_idx,
+                depth,
+                &mut alpha.clone(),
+                &mut beta.clone(),
+                num_seen,
+            );
+            expected += probability * v;
+        }
+        return expected;
+    }
+
     if state.current_player_idx == player_idx {
         let mut best: f64 = -100_000.0;
         for (action, _) in &actions {
@@ -63,8 +83,11 @@ pub fn search(
     }
 }
 
+/// # Panics
+/// If called on a chance node, those are resolved with `State::sample_chance`
 #[must_use]
 pub fn best_move(state: &State) -> Option<Action> {
+    assert!(!state.is_chance_node());
     let actions = Action::next_choices(state);
     if actions.is_empty() {
         println!("GAME OVER");
@@ -98,7 +121,12 @@ pub fn best_move(state: &State) -> Option<Action> {
         }
     }
 
-    println!("Player {player_idx} chooses Action {best_action:?}. Position searched {num_seen}");
+    if let Some(action) = &best_action {
+        println!(
+            "Player {player_idx} chooses Action {}. Position searched {num_seen}",
+            action.describe(state)
+        );
+    }
 
     best_action
 }
@@ -0,0 +1,440 @@
+use super::actions::Action;
+use super::algorithms::PlayerType;
+use super::card::NUM_CARDS;
+use super::farm::{FarmyardSpace, Seed, NUM_FARMYARD_SPACES};
+use super::fencing::{get_existing_pastures, is_future_extension, remove_farmyard_idx};
+use super::flag::NUM_FLAGS;
+use super::notation::{format_goods, parse_goods, parse_num};
+use super::quantity::NUM_QUANTITIES;
+use super::ruleset::Ruleset;
+use super::state::{State, MAX_NUM_PLAYERS};
+
+// Fields before the players, and the last action after them
+const NUM_HEADER_FIELDS: usize = 8;
+
+fn format_bits(bits: &[bool]) -> String {
+    bits.iter().map(|b| if *b { '1' } else { '0' }).collect()
+}
+
+fn parse_bits(s: &str, out: &mut [bool]) -> Result<(), String> {
+    if s.len() != out.len() {
+        return Err(format!("'{s}' should have {} digits", out.len()));
+    }
+    for (bit, c) in out.iter_mut().zip(s.chars()) {
+        *bit = match c {
+            '0' => false,
+            '1' => true,
+            _ => return Err(format!("'{s}' should only have 0 and 1")),
+        };
+    }
+    Ok(())
+}
+
+// Optional list field, '-' when empty
+fn format_list(items: Vec<String>, sep: &str) -> String {
+    if items.is_empty() {
+        String::from("-")
+    } else {
+        items.join(sep)
+    }
+}
+
+fn list_items(s: &str, sep: char) -> Vec<&str> {
+    if s == "-" {
+        Vec::new()
+    } else {
+        s.split(sep).collect()
+    }
+}
+
+fn format_farmyard(spaces: &[FarmyardSpace]) -> String {
+    spaces
+        .iter()
+        .map(|space| match space {
+            FarmyardSpace::Empty => String::from("."),
+            FarmyardSpace::Room => String::from("R"),
+            FarmyardSpace::Field(None) => String::from("F"),
+            FarmyardSpace::Field(Some((Seed::Grain, n))) => format!("G{n}"),
+            FarmyardSpace::Field(Some((Seed::Vegetable, n))) => format!("V{n}"),
+            FarmyardSpace::UnfencedStable => String::from("S"),
+            FarmyardSpace::FencedPasture(false, idx) => format!("p{idx}"),
+            FarmyardSpace::FencedPasture(true, idx) => format!("P{idx}"),
+        })
+        .collect()
+}
+
+fn parse_farmyard(s: &str) -> Result<[FarmyardSpace; NUM_FARMYARD_SPACES], String> {
+    let mut ret = Vec::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        let mut digit = || {
+            chars
+                .next()
+                .and_then(|d| d.to_digit(10))
+                .map(|d| d as usize)
+                .ok_or_else(|| format!("'{c}' needs a digit in farmyard '{s}'"))
+        };
+        ret.push(match c {
+            '.' => FarmyardSpace::Empty,
+            'R' => FarmyardSpace::Room,
+            'F' => FarmyardSpace::Field(None),
+            'G' => FarmyardSpace::Field(Some((Seed::Grain, digit()?))),
+            'V' => FarmyardSpace::Field(Some((Seed::Vegetable, digit()?))),
+            'S' => FarmyardSpace::UnfencedStable,
+            'p' => FarmyardSpace::FencedPasture(false, digit()?),
+            'P' => FarmyardSpace::FencedPasture(true, digit()?),
+            _ => return Err(format!("Unknown farmyard space '{c}' in '{s}'")),
+        });
+    }
+    ret.try_into()
+        .map_err(|_| format!("Farmyard '{s}' should have {NUM_FARMYARD_SPACES} spaces"))
+}
+
+fn player_type_letter(player_type: PlayerType) -> char {
+    match player_type {
+        PlayerType::Human => 'H',
+        PlayerType::MctsAI => 'M',
+        PlayerType::TdAI => 'T',
+        PlayerType::External => 'E',
+    }
+}
+
+fn format_player(state: &State, player_idx: usize) -> String {
+    let quantities = state
+        .player_quantities(player_idx)
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+    let farm = state.player_farm(player_idx);
+    format!(
+        "{}:{quantities}:{}:{}:{}:{}",
+        player_type_letter(state.player_type(player_idx)),
+        format_bits(state.player_flags(player_idx)),
+        format_bits(state.player_cards(player_idx)),
+        format_farmyard(&farm.farmyard_spaces),
+        farm.fences_used
+    )
+}
+
+fn parse_player_type(s: &str) -> Result<PlayerType, String> {
+    match s {
+        "H" => Ok(PlayerType::Human),
+        "M" => Ok(PlayerType::MctsAI),
+        "T" => Ok(PlayerType::TdAI),
+        "E" => Ok(PlayerType::External),
+        _ => Err(format!("Unknown player type '{s}'")),
+    }
+}
+
+fn parse_player(state: &mut State, player_idx: usize, s: &str) -> Result<(), String> {
+    let parts: Vec<&str> = s.split(':').collect();
+    let [_, quantities, flags, cards, farmyard, fences] = parts[..] else {
+        return Err(format!("Player '{s}' should have 6 parts"));
+    };
+
+    let quantities: Vec<usize> = quantities
+        .split(',')
+        .map(parse_num)
+        .collect::<Result<_, _>>()?;
+    *state.player_quantities_mut(player_idx) = quantities
+        .try_into()
+        .map_err(|_| format!("Player '{s}' should have {NUM_QUANTITIES} quantities"))?;
+
+    let mut player_flags = [false; NUM_FLAGS];
+    parse_bits(flags, &mut player_flags)?;
+    *state.player_flags_mut(player_idx) = player_flags;
+    let mut player_cards = [false; NUM_CARDS];
+    parse_bits(cards, &mut player_cards)?;
+    *state.player_cards_mut(player_idx) = player_cards;
+
+    let farm = state.player_farm_mut(player_idx);
+    farm.farmyard_spaces = parse_farmyard(farmyard)?;
+    farm.fences_used = parse_num(fences)?;
+    state.restore_fence_options(player_idx);
+    Ok(())
+}
+
+impl State {
+    /// Rebuilds the fence options of a farm set up directly, from the options of an empty farm.
+    /// They are narrowed down the way playing the rooms, fields and fences would have.
+    pub(crate) fn restore_fence_options(&mut self, player_idx: usize) {
+        let spaces = self.player_farm(player_idx).farmyard_spaces;
+        let cache = &mut self.fence_options_cache[player_idx];
+        for (idx, space) in spaces.iter().enumerate() {
+            if matches!(space, FarmyardSpace::Room | FarmyardSpace::Field(_)) {
+                remove_farmyard_idx(cache, idx);
+            }
+        }
+        let existing_pastures = get_existing_pastures(&spaces);
+        if !existing_pastures.is_empty() {
+            cache.retain(|x| is_future_extension(&x.pastures, &existing_pastures));
+        }
+    }
+
+    /// The position as a single line, like FEN in chess. Space separated fields :
+    /// seed, round, `h` if the harvest of the round is done (`-` otherwise), current / starting player and workers placed,
+    /// revealed round cards, the occupant of each action space, accumulated goods, scheduled goods,
+    /// one field per player (type H, M, T or E, quantities, flags, cards, farmyard, fences used) and the last action in `full_notation`.
+    /// A ruleset other than the standard one follows as JSON. The state of the random number generator is not part of the position.
+    /// # Panics
+    /// If the ruleset cannot be serialized
+    #[must_use]
+    pub fn to_position_str(&self) -> String {
+        let revealed = self.revealed_action_spaces()[self.ruleset().num_open_spaces()..]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let occupied: String = self
+            .occupied
+            .iter()
+            .map(|o| o.map_or('-', |p| char::from_digit(p as u32, 10).unwrap()))
+            .collect();
+        let accumulated = self
+            .accumulated_resources
+            .iter()
+            .enumerate()
+            .filter(|(_, res)| res.iter().any(|n| *n > 0))
+            .map(|(idx, res)| format!("{idx}={}", format_goods(res)))
+            .collect();
+        let scheduled = self
+            .scheduled_goods
+            .iter()
+            .map(|g| {
+                format!(
+                    "{}.{}={}",
+                    g.round,
+                    g.player_idx,
+                    format_goods(&g.resources)
+                )
+            })
+            .collect();
+
+        let mut fields = vec![
+            self.seed().to_string(),
+            self.current_round.to_string(),
+            String::from(if self.harvest_done { "h" } else { "-" }),
+            format!(
+                "{},{},{}",
+                self.current_player_idx, self.starting_player_idx, self.people_placed_this_round
+            ),
+            format_list(revealed, ","),
+            occupied,
+            format_list(accumulated, ";"),
+            format_list(scheduled, ";"),
+        ];
+        fields.extend((0..self.num_players).map(|i| format_player(self, i)));
+        fields.push(self.last_action.full_notation());
+        if *self.ruleset() != Ruleset::default() {
+            fields.push(serde_json::to_string(self.ruleset()).unwrap());
+        }
+        fields.join(" ")
+    }
+
+    /// Sets up a state from a position written by `to_position_str`
+    /// # Errors
+    /// If the position is malformed or describes an inconsistent state
+    pub fn from_position_str(position: &str) -> Result<Self, String> {
+        // The ruleset is the only field with braces, and its names may have spaces
+        let (position, ruleset) = match position.find('{') {
+            Some(i) => (&position[..i], Ruleset::from_json(&position[i..])?),
+            None => (position, Ruleset::default()),
+        };
+        let fields: Vec<&str> = position.split_whitespace().collect();
+        if fields.len() < NUM_HEADER_FIELDS + 2 {
+            return Err(String::from("Position has too few fields"));
+        }
+        let num_players = fields.len() - NUM_HEADER_FIELDS - 1;
+        if num_players > MAX_NUM_PLAYERS {
+            return Err(format!("At most {MAX_NUM_PLAYERS} players are supported"));
+        }
+        let player_fields = &fields[NUM_HEADER_FIELDS..NUM_HEADER_FIELDS + num_players];
+        let players = player_fields
+            .iter()
+            .map(|p| parse_player_type(p.split(':').next().unwrap_or_default()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let seed = parse_num(fields[0])?;
+        let mut state = Self::with_ruleset_and_seed(&players, ruleset, seed)
+            .ok_or("Position has no players")?;
+
+        state.current_round = parse_num(fields[1])?;
+        if state.current_round > state.ruleset().num_rounds() {
+            return Err(format!("Round {} does not exist", state.current_round));
+        }
+        state.harvest_done = match fields[2] {
+            "h" => true,
+            "-" => false,
+            s => return Err(format!("Harvest field should be 'h' or '-', not '{s}'")),
+        };
+
+        let turn: Vec<usize> = fields[3]
+            .split(',')
+            .map(parse_num)
+            .collect::<Result<_, _>>()?;
+        let [current, starting, placed] = turn[..] else {
+            return Err(format!("'{}' should be current,starting,placed", fields[3]));
+        };
+        if current >= num_players || starting >= num_players {
+            return Err(format!("Players in '{}' do not exist", fields[3]));
+        }
+        state.current_player_idx = current;
+        state.starting_player_idx = starting;
+        state.people_placed_this_round = placed;
+
+        let revealed = list_items(fields[4], ',');
+        if revealed.len() != state.current_round {
+            return Err(format!(
+                "{} round cards should be revealed by round {}",
+                revealed.len(),
+                state.current_round
+            ));
+        }
+        for (i, card) in revealed.iter().enumerate() {
+            let card_idx = parse_num(card)?;
+            if !state.round_card_candidates(i + 1).contains(&card_idx) {
+                return Err(format!(
+                    "Round card {card_idx} cannot be revealed in round {}",
+                    i + 1
+                ));
+            }
+            state.reveal_round_card(i + 1, card_idx);
+        }
+
+        let num_action_spaces = state.ruleset().num_action_spaces();
+        if fields[5].chars().count() != num_action_spaces {
+            return Err(format!(
+                "Occupied spaces should have {num_action_spaces} entries"
+            ));
+        }
+        for (occupant, c) in state.occupied.iter_mut().zip(fields[5].chars()) {
+            *occupant = match c {
+                '-' => None,
+                _ => Some(
+                    c.to_digit(10)
+                        .map(|d| d as usize)
+                        .filter(|d| *d < num_players)
+                        .ok_or_else(|| format!("Unknown occupant '{c}'"))?,
+                ),
+            };
+        }
+
+        for item in list_items(fields[6], ';') {
+            let (idx, goods) = item
+                .split_once('=')
+                .ok_or_else(|| format!("Accumulated goods '{item}' should be space=goods"))?;
+            let idx: usize = parse_num(idx)?;
+            if idx >= num_action_spaces {
+                return Err(format!("Action space {idx} does not exist"));
+            }
+            state.accumulated_resources[idx] = parse_goods(goods)?;
+        }
+
+        for item in list_items(fields[7], ';') {
+            let (key, goods) = item
+                .split_once('=')
+                .ok_or_else(|| format!("Scheduled goods '{item}' should be round.player=goods"))?;
+            let (round, player_idx) = key
+                .split_once('.')
+                .ok_or_else(|| format!("Scheduled goods '{item}' should be round.player=goods"))?;
+            let player_idx = parse_num(player_idx)?;
+            if player_idx >= num_players {
+                return Err(format!("Player {player_idx} does not exist"));
+            }
+            state.schedule_goods(player_idx, parse_num(round)?, &parse_goods(goods)?);
+        }
+
+        for (player_idx, field) in player_fields.iter().enumerate() {
+            parse_player(&mut state, player_idx, field)?;
+        }
+
+        state.last_action = Action::from_full_notation(fields[fields.len() - 1])
+            .map_err(|e| format!("Last action : {e}"))?;
+        // Moves past the action spaces, the cards or the farmyard would panic when the game goes on
+        if !state.last_action.fits_ruleset(state.ruleset()) {
+            return Err(format!(
+                "Last action : {:?} cannot be part of a game with this ruleset",
+                state.last_action
+            ));
+        }
+
+        let violations = state.validate();
+        if !violations.is_empty() {
+            return Err(violations
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", "));
+        }
+        Ok(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_round_trip() {
+        let mut state = State::with_seed(&[PlayerType::Human, PlayerType::MctsAI], 41).unwrap();
+        for _ in 0..3 {
+            state.play_random(Some(40));
+            if Action::next_choices(&state).is_empty() {
+                break;
+            }
+            let position = state.to_position_str();
+            let loaded = State::from_position_str(&position).unwrap();
+            assert_eq!(loaded.to_position_str(), position);
+            assert_eq!(loaded.get_hash(), state.get_hash());
+            assert_eq!(
+                Action::next_choices(&loaded).len(),
+                Action::next_choices(&state).len()
+            );
+        }
+    }
+
+    #[test]
+    fn test_position_with_ruleset() {
+        let ruleset = Ruleset {
+            harvest_rounds: vec![2, 4, 6, 8, 10, 12, 14],
+            max_stables: 3,
+            ..Default::default()
+        };
+        let mut state =
+            State::with_ruleset_and_seed(&[PlayerType::Human; 2], ruleset.clone(), 47).unwrap();
+        state.play_random(Some(30));
+
+        let position = state.to_position_str();
+        let loaded = State::from_position_str(&position).unwrap();
+        assert_eq!(*loaded.ruleset(), ruleset);
+        assert_eq!(loaded.to_position_str(), position);
+        assert_eq!(loaded.get_hash(), state.get_hash());
+
+        // The standard ruleset is left out
+        let standard = State::with_seed(&[PlayerType::Human], 47).unwrap();
+        assert!(!standard.to_position_str().contains('{'));
+    }
+
+    #[test]
+    fn test_bad_position() {
+        let state = State::with_seed(&[PlayerType::Human], 43).unwrap();
+        let position = state.to_position_str();
+        assert!(State::from_position_str(&position).is_ok());
+        assert!(State::from_position_str("").is_err());
+
+        // Three rooms on the farmyard but two in the quantities
+        let bad = position.replacen("R....R", "R...RR", 1);
+        assert_ne!(bad, position);
+        assert!(State::from_position_str(&bad).is_err());
+
+        // Last actions past the action spaces or the occupations
+        let last_action = position.rsplit(' ').next().unwrap();
+        for bad_action in ["U999", "OC99~1"] {
+            let bad = format!(
+                "{}{bad_action}",
+                position.strip_suffix(last_action).unwrap()
+            );
+            assert!(State::from_position_str(&bad).is_err());
+        }
+    }
+}
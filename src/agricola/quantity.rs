@@ -1,3 +1,6 @@
+use super::actions::RuleError;
+use serde::{Deserialize, Serialize};
+
 pub trait Quantity {
     fn index(&self) -> usize;
 }
@@ -145,6 +148,19 @@ impl Quantity for BeggingTokens {
 
 pub const NUM_QUANTITIES: usize = 20;
 pub const NUM_RESOURCES: usize = 10;
+/// Names of the resources in index order, as written in the action space data
+pub const RESOURCE_NAMES: [&str; NUM_RESOURCES] = [
+    "Wood",
+    "Clay",
+    "Stone",
+    "Food",
+    "Reed",
+    "Grain",
+    "Vegetable",
+    "Sheep",
+    "Boar",
+    "Cattle",
+];
 
 pub type Resources = [usize; NUM_RESOURCES];
 pub type Quantities = [usize; NUM_QUANTITIES];
@@ -184,14 +200,17 @@ pub fn can_pay_for_resource(cost: &Resources, store: &Quantities) -> bool {
     true
 }
 
-/// # Panics
-/// If assertion `can_pay_for_resource` returns false
-pub fn pay_for_resource(cost: &Resources, store: &mut Quantities) {
-    assert!(can_pay_for_resource(cost, store));
+/// # Errors
+/// If `store` does not hold `cost`, nothing is paid then
+pub fn pay_for_resource(cost: &Resources, store: &mut Quantities) -> Result<(), RuleError> {
+    if !can_pay_for_resource(cost, store) {
+        return Err(RuleError::NotEnoughResources(*cost));
+    }
     for it in cost.iter().zip(store.iter_mut()).take(NUM_RESOURCES) {
         let (a, b) = it;
         *b -= a;
     }
+    Ok(())
 }
 
 pub fn take_resource(res: &Resources, store: &mut Quantities) {
@@ -201,7 +220,7 @@ pub fn take_resource(res: &Resources, store: &mut Quantities) {
     }
 }
 
-#[derive(Debug, Clone, Hash)]
+#[derive(Debug, Clone, Hash, PartialEq, Serialize, Deserialize)]
 pub struct ResourceExchange {
     pub from: usize,
     pub to: usize,
@@ -0,0 +1,185 @@
+use super::action_space::{
+    num_open_spaces, standard_action_spaces, validate_action_spaces, ActionSpaceDef,
+};
+use super::farm::{MAX_FENCES, MAX_STABLES, NUM_FARMYARD_SPACES};
+use super::scoring::{
+    CATTLE_SCORE, FIELD_SCORE, GRAIN_SCORE, HOUSE_SCORE, PASTURE_SCORE, PIGS_SCORE, SHEEP_SCORE,
+    VEGETABLE_SCORE,
+};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+const HARVEST_ROUNDS: [usize; 6] = [4, 7, 9, 11, 13, 14];
+const MAX_FAMILY_MEMBERS: usize = 5;
+
+/// The numbers that define a game. Defaults to the standard rules, can be loaded from a JSON file for house rules.
+/// Each scoring table gives the points for 0, 1, 2.. of something, the last entry is used for anything beyond.
+/// The action spaces are open spaces followed by the round cards, one round card is revealed each round.
+#[derive(Clone, Debug, Hash, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Ruleset {
+    pub field_score: Vec<i32>,
+    pub pasture_score: Vec<i32>,
+    pub grain_score: Vec<i32>,
+    pub vegetable_score: Vec<i32>,
+    pub sheep_score: Vec<i32>,
+    pub pigs_score: Vec<i32>,
+    pub cattle_score: Vec<i32>,
+    // Points per room, indexed by house material : Wood, Clay, Stone
+    pub house_score: [i32; 3],
+    pub harvest_rounds: Vec<usize>,
+    pub max_family_members: usize,
+    pub max_stables: usize,
+    pub max_fences: usize,
+    pub action_spaces: Vec<ActionSpaceDef>,
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Self {
+            field_score: FIELD_SCORE.to_vec(),
+            pasture_score: PASTURE_SCORE.to_vec(),
+            grain_score: GRAIN_SCORE.to_vec(),
+            vegetable_score: VEGETABLE_SCORE.to_vec(),
+            sheep_score: SHEEP_SCORE.to_vec(),
+            pigs_score: PIGS_SCORE.to_vec(),
+            cattle_score: CATTLE_SCORE.to_vec(),
+            house_score: HOUSE_SCORE,
+            harvest_rounds: HARVEST_ROUNDS.to_vec(),
+            max_family_members: MAX_FAMILY_MEMBERS,
+            max_stables: MAX_STABLES,
+            max_fences: MAX_FENCES,
+            action_spaces: standard_action_spaces(),
+        }
+    }
+}
+
+impl Ruleset {
+    /// Parse a ruleset from JSON. Missing fields take their standard values.
+    /// # Errors
+    /// If the JSON is malformed or the ruleset is not valid
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let ruleset: Self = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        ruleset.validate()?;
+        Ok(ruleset)
+    }
+
+    /// # Errors
+    /// If the file cannot be read or does not hold a valid ruleset
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let json = fs::read_to_string(path.as_ref())
+            .map_err(|e| format!("{}: {e}", path.as_ref().display()))?;
+        Self::from_json(&json)
+    }
+
+    /// # Errors
+    /// If any of the numbers cannot be supported by the engine
+    pub fn validate(&self) -> Result<(), String> {
+        let tables = [
+            ("field_score", &self.field_score),
+            ("pasture_score", &self.pasture_score),
+            ("grain_score", &self.grain_score),
+            ("vegetable_score", &self.vegetable_score),
+            ("sheep_score", &self.sheep_score),
+            ("pigs_score", &self.pigs_score),
+            ("cattle_score", &self.cattle_score),
+        ];
+        for (name, table) in tables {
+            if table.is_empty() {
+                return Err(format!("{name} is empty"));
+            }
+        }
+
+        validate_action_spaces(&self.action_spaces)?;
+        let num_rounds = self.num_rounds();
+        if let Some(round) = self
+            .harvest_rounds
+            .iter()
+            .find(|r| **r == 0 || **r > num_rounds)
+        {
+            return Err(format!("Harvest round {round} is not in 1..={num_rounds}"));
+        }
+        if self.max_family_members < 2 {
+            return Err(String::from("max_family_members must be at least 2"));
+        }
+        if self.max_stables > NUM_FARMYARD_SPACES {
+            return Err(format!("max_stables must be at most {NUM_FARMYARD_SPACES}"));
+        }
+        if self.max_fences > MAX_FENCES {
+            return Err(format!("max_fences must be at most {MAX_FENCES}"));
+        }
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn num_action_spaces(&self) -> usize {
+        self.action_spaces.len()
+    }
+
+    /// Action spaces open from the start
+    #[must_use]
+    pub fn num_open_spaces(&self) -> usize {
+        num_open_spaces(&self.action_spaces)
+    }
+
+    /// One round per round card
+    #[must_use]
+    pub fn num_rounds(&self) -> usize {
+        self.num_action_spaces() - self.num_open_spaces()
+    }
+
+    #[must_use]
+    pub fn is_harvest_round(&self, round: usize) -> bool {
+        self.harvest_rounds.contains(&round)
+    }
+}
+
+/// Points from a scoring table, anything past the end of the table scores like the last entry
+#[must_use]
+pub fn table_score(table: &[i32], count: usize) -> i32 {
+    table[count.min(table.len() - 1)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_ruleset() {
+        let ruleset =
+            Ruleset::from_json(r#"{ "harvest_rounds": [7, 14], "max_fences": 9 }"#).unwrap();
+        assert!(ruleset.is_harvest_round(7));
+        assert!(!ruleset.is_harvest_round(4));
+        assert_eq!(ruleset.max_fences, 9);
+        assert_eq!(ruleset.field_score, Ruleset::default().field_score);
+
+        assert!(Ruleset::from_json(r#"{ "max_fences": 16 }"#).is_err());
+        assert!(Ruleset::from_json(r#"{ "harvest_rounds": [15] }"#).is_err());
+    }
+
+    #[test]
+    fn test_custom_action_spaces() {
+        let mut spaces = serde_json::to_value(standard_action_spaces()).unwrap();
+        let spaces = spaces.as_array_mut().unwrap();
+        // A shorter game without the last stage, and an extra open space
+        spaces.pop();
+        spaces.insert(
+            0,
+            serde_json::json!({ "name": "Quarry", "stage": 0, "accumulation": { "Stone": 1 } }),
+        );
+        let json =
+            serde_json::json!({ "harvest_rounds": [4, 7, 9, 11, 13], "action_spaces": spaces });
+        let ruleset = Ruleset::from_json(&json.to_string()).unwrap();
+        assert_eq!(ruleset.num_action_spaces(), 30);
+        assert_eq!(ruleset.num_open_spaces(), 17);
+        assert_eq!(ruleset.num_rounds(), 13);
+
+        // Harvests must fall within the rounds and round cards must be ordered by stage
+        let json = serde_json::json!({ "action_spaces": spaces });
+        assert!(Ruleset::from_json(&json.to_string()).is_err());
+        spaces.swap(17, 29);
+        let json = serde_json::json!({ "harvest_rounds": [4], "action_spaces": spaces });
+        assert!(Ruleset::from_json(&json.to_string()).is_err());
+    }
+}
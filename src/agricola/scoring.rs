@@ -1,19 +1,21 @@
 use super::farm::{FarmyardSpace, Seed};
 use super::fencing::get_existing_pastures;
 use super::quantity::{Boar, Cattle, Grain, Quantity, Sheep, Vegetable};
+use super::ruleset::table_score;
 use super::state::State;
 
 pub const FIELD_SCORE: [i32; 6] = [-1, -1, 1, 2, 3, 4];
 pub const PASTURE_SCORE: [i32; 5] = [-1, 1, 2, 3, 4];
-const GRAIN_SCORE: [i32; 9] = [-1, 1, 1, 1, 2, 2, 3, 3, 4];
-const VEGETABLE_SCORE: [i32; 5] = [-1, 1, 2, 3, 4];
-const SHEEP_SCORE: [i32; 9] = [-1, 1, 1, 1, 2, 2, 3, 3, 4];
-const PIGS_SCORE: [i32; 8] = [-1, 1, 1, 2, 2, 3, 3, 4];
-const CATTLE_SCORE: [i32; 7] = [-1, 1, 2, 2, 3, 3, 4];
-const HOUSE_SCORE: [i32; 3] = [0, 1, 2];
+pub const GRAIN_SCORE: [i32; 9] = [-1, 1, 1, 1, 2, 2, 3, 3, 4];
+pub const VEGETABLE_SCORE: [i32; 5] = [-1, 1, 2, 3, 4];
+pub const SHEEP_SCORE: [i32; 9] = [-1, 1, 1, 1, 2, 2, 3, 3, 4];
+pub const PIGS_SCORE: [i32; 8] = [-1, 1, 1, 2, 2, 3, 3, 4];
+pub const CATTLE_SCORE: [i32; 7] = [-1, 1, 2, 2, 3, 3, 4];
+pub const HOUSE_SCORE: [i32; 3] = [0, 1, 2];
 
 #[must_use]
 pub fn score_farm(state: &State, player_idx: usize) -> i32 {
+    let rules = state.ruleset();
     let mut score = 0;
     let mut num_fields: usize = 0;
     let mut player_quantities = *state.player_quantities(player_idx);
@@ -28,12 +30,8 @@ pub fn score_farm(state: &State, player_idx: usize) -> i32 {
     for space in &state.player_farm(player_idx).farmyard_spaces {
         match *space {
             FarmyardSpace::Empty => score -= 1,
-            FarmyardSpace::Room => score += HOUSE_SCORE[house_type_idx],
-            FarmyardSpace::FencedPasture(has_stable, _) => {
-                if has_stable {
-                    score += 1;
-                }
-            }
+            FarmyardSpace::Room => score += rules.house_score[house_type_idx],
+            FarmyardSpace::FencedPasture(true, _) => score += 1,
             FarmyardSpace::Field(Some((seed, amt))) => {
                 num_fields += 1;
                 match seed {
@@ -45,13 +43,12 @@ pub fn score_farm(state: &State, player_idx: usize) -> i32 {
         }
     }
 
-    score += PASTURE_SCORE[num_pastures.min(PASTURE_SCORE.len() - 1)];
-    score += FIELD_SCORE[num_fields.min(FIELD_SCORE.len() - 1)];
-    score += GRAIN_SCORE[player_quantities[Grain.index()].min(GRAIN_SCORE.len() - 1)];
-    score += VEGETABLE_SCORE[player_quantities[Vegetable.index()].min(VEGETABLE_SCORE.len() - 1)];
-    score += SHEEP_SCORE[player_quantities[Sheep.index()].min(SHEEP_SCORE.len() - 1)];
-    score += PIGS_SCORE[player_quantities[Boar.index()].min(PIGS_SCORE.len() - 1)];
-    score += CATTLE_SCORE[player_quantities[Cattle.index()].min(CATTLE_SCORE.len() - 1)];
-
+    score += table_score(&rules.pasture_score, num_pastures);
+    score += table_score(&rules.field_score, num_fields);
+    score += table_score(&rules.grain_score, player_quantities[Grain.index()]);
+    score += table_score(&rules.vegetable_score, player_quantities[Vegetable.index()]);
+    score += table_score(&rules.sheep_score, player_quantities[Sheep.index()]);
+    score += table_score(&rules.pigs_score, player_quantities[Boar.index()]);
+    score += table_score(&rules.cattle_score, player_quantities[Cattle.index()]);
     score
 }
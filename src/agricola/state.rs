@@ -1,55 +1,63 @@
 use crate::agricola::fencing::{is_future_extension, remove_farmyard_idx};
 
 use super::action_space::{
-    accumulate, randomize_action_spaces, ACCUMULATION_SPACE_INDICES, ACTION_SPACE_NAMES,
-    NUM_ACTION_SPACES, OPEN_SPACES,
+    reveal_round_card, round_card_candidates, take_resources, upcoming_round_cards, ActionSpaceDef,
+    FollowUp,
 };
-use super::actions::Action;
+use super::actions::{Action, RuleError};
 use super::algorithms::PlayerType;
 use super::card::{
-    cost, points, Card, ClayOven, CookingHearth1, CookingHearth2, Fireplace1, Fireplace2,
-    StoneOven, Well, BAKING_IMPROVEMENTS_INDICES, CARD_NAMES, COOKING_HEARTH_INDICES,
-    COOKING_IMPROVEMENTS_INDICES, FIREPLACE_INDICES, MAJOR_IMPROVEMENTS_INDICES, NUM_CARDS,
-    OCCUPATIONS_INDICES,
+    allocation_grain, baking_allocations, baking_capacity, baking_food, best_baking_allocation,
+    cost, future_goods, points, renovation_options, sow_amount, BakingAllocation, Card,
+    CookingHearth1, CookingHearth2, Fireplace1, Fireplace2, Renovation,
+    BAKING_IMPROVEMENTS_INDICES, CARD_NAMES, COOKING_HEARTH_INDICES, COOKING_IMPROVEMENTS_INDICES,
+    FIREPLACE_INDICES, MAJOR_IMPROVEMENTS_INDICES, NUM_CARDS, OCCUPATIONS_INDICES,
 };
 use super::display::format_resources;
-use super::farm::{Farm, FarmyardSpace, Seed, NUM_FARMYARD_SPACES};
+use super::farm::{Farm, FarmyardSpace, Seed, SowingPlan, NUM_FARMYARD_SPACES};
+use super::feeding::{feeding_plans, FeedingPlan};
 use super::fencing::{get_all_pasture_configs, PastureConfig};
 use super::flag::{
-    BakedOnceWithClayOven, BakedOnceWithStoneOven, BakedTwiceWithStoneOven, BeforeRoundStart,
-    ClayHouse, Flag, HarvestPaid, HasCookingImprovement, HasRoomToGrow, StoneHouse,
-    UsedBasketmakersWorkshop, UsedJoinery, UsedPottery, WoodHouse, NUM_FLAGS,
+    BeforeRoundStart, ClayHouse, Flag, HarvestPaid, HasCookingImprovement, HasRoomToGrow,
+    StoneHouse, WoodHouse, NUM_FLAGS,
 };
 use super::quantity::{
-    can_pay_for_resource, pay_for_resource, AdultMembers, BeggingTokens, Boar, Cattle, Children,
-    Clay, Food, Grain, MembersPlacedThisRound, Quantities, Quantity, Reed, ResourceExchange,
-    Resources, Rooms, Sheep, Stone, Vegetable, Wood, NUM_QUANTITIES, NUM_RESOURCES,
+    can_pay_for_resource, new_res, pay_for_resource, take_resource, AdultMembers, BeggingTokens,
+    Boar, Cattle, Children, Clay, Food, Grain, MembersPlacedThisRound, Quantity, Reed,
+    ResourceExchange, Resources, Rooms, Sheep, Stone, Vegetable, Wood, NUM_QUANTITIES,
+    NUM_RESOURCES,
 };
+use super::ruleset::Ruleset;
 use super::scoring::score_farm;
+use super::validation::{validate, Violation};
 use core::panic;
 use derivative::Derivative;
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
+use std::sync::Arc;
 
 pub const MAX_NUM_PLAYERS: usize = 4;
-const MAX_FAMILY_MEMBERS: usize = 5;
 const EPSILON: f64 = 1e-6;
 
-#[derive(Clone, Hash, Debug)]
-pub struct Event {
+/// Goods promised to a player on the space of a future round, delivered when that round starts
+#[derive(Clone, Debug, Hash, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledGoods {
     pub round: usize,
     pub player_idx: usize,
-    pub func: fn(res: &mut [usize; NUM_QUANTITIES]),
+    pub resources: Resources,
 }
 
-#[derive(Clone, Derivative, Hash)]
+#[derive(Clone, Derivative, Serialize)]
+#[derivative(Hash)]
 pub struct State {
     pub num_players: usize,
     pub current_round: usize,
-    pub accumulated_resources: [Resources; NUM_ACTION_SPACES], // Only accumulation spaces are used
-    pub action_spaces: [usize; NUM_ACTION_SPACES],
-    pub occupied: [Option<usize>; NUM_ACTION_SPACES],
+    pub accumulated_resources: Vec<Resources>, // Only accumulation spaces are used
+    pub action_spaces: Vec<usize>,
+    pub occupied: Vec<Option<usize>>,
     pub harvest_done: bool,
     player_types: [PlayerType; MAX_NUM_PLAYERS],
     player_quantities: [[usize; NUM_QUANTITIES]; MAX_NUM_PLAYERS],
@@ -60,9 +68,21 @@ pub struct State {
     pub starting_player_idx: usize,
     pub people_placed_this_round: usize,
     pub last_action: Action,
-    pub start_round_events: Vec<Event>,
+    pub scheduled_goods: Vec<ScheduledGoods>,
     #[derivative(Hash = "ignore")]
+    #[serde(skip)]
+    ruleset: Arc<Ruleset>,
+    seed: u64,
+    #[derivative(Hash = "ignore")]
+    #[serde(skip)]
+    rng: ChaCha8Rng,
+    #[derivative(Hash = "ignore")]
+    #[serde(skip)]
     pub fence_options_cache: [Vec<PastureConfig>; MAX_NUM_PLAYERS],
+    // When set, every applied action is followed by a full validation of the state
+    #[derivative(Hash = "ignore")]
+    #[serde(skip)]
+    pub debug_validate: bool,
 }
 
 impl State {
@@ -70,11 +90,38 @@ impl State {
     /// Will panic if initialization fails
     #[must_use]
     pub fn new(players: &[PlayerType]) -> Option<Self> {
+        Self::with_ruleset_and_seed(players, Ruleset::default(), rand::random())
+    }
+
+    /// # Panics
+    /// Will panic if initialization fails
+    #[must_use]
+    pub fn with_seed(players: &[PlayerType], seed: u64) -> Option<Self> {
+        Self::with_ruleset_and_seed(players, Ruleset::default(), seed)
+    }
+
+    /// # Panics
+    /// Will panic if initialization fails
+    #[must_use]
+    pub fn with_ruleset(players: &[PlayerType], ruleset: Ruleset) -> Option<Self> {
+        Self::with_ruleset_and_seed(players, ruleset, rand::random())
+    }
+
+    /// Every random decision of the game is drawn from `seed`, so the seed and the list of actions replay a game exactly
+    /// # Panics
+    /// Will panic if initialization fails
+    #[must_use]
+    pub fn with_ruleset_and_seed(
+        players: &[PlayerType],
+        ruleset: Ruleset,
+        seed: u64,
+    ) -> Option<Self> {
         if players.is_empty() {
             return None;
         }
 
-        let first_player_idx = rand::thread_rng().gen_range(0..players.len());
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let first_player_idx = rng.gen_range(0..players.len());
         let mut player_quantities = [[0; NUM_QUANTITIES]; MAX_NUM_PLAYERS];
         for (i, player_quantities) in player_quantities.iter_mut().enumerate().take(players.len()) {
             if i == first_player_idx {
@@ -99,15 +146,13 @@ impl State {
         let farm = Farm::new();
         let all_fence_options = get_all_pasture_configs(&farm.farmyard_spaces);
 
+        let num_action_spaces = ruleset.num_action_spaces();
         let state = State {
             num_players: players.len(),
             current_round: 0,
-            accumulated_resources: [[0; NUM_RESOURCES]; NUM_ACTION_SPACES],
-            action_spaces: (0..NUM_ACTION_SPACES)
-                .collect::<Vec<usize>>()
-                .try_into()
-                .unwrap(),
-            occupied: [None; NUM_ACTION_SPACES],
+            accumulated_resources: vec![[0; NUM_RESOURCES]; num_action_spaces],
+            action_spaces: (0..num_action_spaces).collect(),
+            occupied: vec![None; num_action_spaces],
             harvest_done: false,
             player_types,
             player_quantities,
@@ -118,24 +163,95 @@ impl State {
             starting_player_idx: first_player_idx,
             people_placed_this_round: 0,
             last_action: Action::StartGame,
-            start_round_events: vec![],
+            scheduled_goods: vec![],
+            ruleset: Arc::new(ruleset),
+            seed,
+            rng,
             fence_options_cache: core::array::from_fn(|_| all_fence_options.clone()),
+            debug_validate: false,
         };
         Some(state)
     }
 
     pub fn add_action(&mut self, action: &Action) {
         // Set space to occupied of action corresponds to an action space
-        if action.action_idx() < NUM_ACTION_SPACES {
-            // This only works because the indices of actions and action spaces are the same.
-            // TODO : Fix this duplication
-            self.occupied[action.action_idx()] = Some(self.current_player_idx);
+        if let Action::UseSpace(idx) = action {
+            self.occupied[*idx] = Some(self.current_player_idx);
         }
 
         // Add action to the sequence of actions taken by the current player
         self.last_action = action.clone();
     }
 
+    /// Applies `action` if it is one of the choices in this state, the state is left untouched otherwise
+    /// # Errors
+    /// If the game is over or the action is not legal here. Actions on the farm, the action spaces and the
+    /// supply report the rule they break, any other illegal action is an `IllegalAction`.
+    pub fn try_apply(&mut self, action: &Action) -> Result<(), RuleError> {
+        let choices = Action::next_choices(self);
+        if choices.is_empty() {
+            return Err(RuleError::GameOver);
+        }
+        let equivalent = action.equivalent_choice(self);
+        if !choices.iter().any(|(choice, _)| *choice == equivalent) {
+            return Err(self.why_illegal(action));
+        }
+        action.apply_choice(self);
+        Ok(())
+    }
+
+    // The rule an illegal action breaks, found by trying it on a copy of the state
+    fn why_illegal(&self, action: &Action) -> RuleError {
+        let checked = matches!(
+            action,
+            Action::UseSpace(_)
+                | Action::BuildRoom(_)
+                | Action::BuildStable(_)
+                | Action::Plow(..)
+                | Action::BuildCard(..)
+                | Action::PlayOccupation(..)
+        );
+        if checked {
+            if let Err(err) = action.apply(&mut self.clone()) {
+                return err;
+            }
+        }
+        RuleError::IllegalAction(action.clone())
+    }
+
+    /// All invariants of the state that are broken, empty for a consistent state
+    #[must_use]
+    pub fn validate(&self) -> Vec<Violation> {
+        validate(self)
+    }
+
+    #[must_use]
+    pub fn ruleset(&self) -> &Ruleset {
+        &self.ruleset
+    }
+
+    #[must_use]
+    pub fn action_space(&self, idx: usize) -> &ActionSpaceDef {
+        &self.ruleset.action_spaces[idx]
+    }
+
+    /// Action spaces that can be used this round : the open spaces and the round cards revealed so far
+    #[must_use]
+    pub fn revealed_action_spaces(&self) -> &[usize] {
+        &self.action_spaces[..self.ruleset.num_open_spaces() + self.current_round]
+    }
+
+    /// The seed the game was created with
+    #[must_use]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Replace the random number generator, used by the AI so that simulations from a copy of the state do not replay the real game
+    pub fn reseed_rng(&mut self, seed: u64) {
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+    }
+
     #[must_use]
     pub fn harvest_paid(&self) -> bool {
         self.current_player_flags()[HarvestPaid.index()]
@@ -144,34 +260,18 @@ impl State {
     #[must_use]
     pub fn can_init_new_round(&self) -> bool {
         // If all stages are done
-        if OPEN_SPACES + self.current_round == NUM_ACTION_SPACES {
+        if self.current_round == self.ruleset.num_rounds() {
             return false;
         }
 
         // If stages left or last stage, but harvest is yet to be completed
-        if (self.current_round == 4
-            || self.current_round == 7
-            || self.current_round == 9
-            || self.current_round == 11
-            || self.current_round == 13
-            || self.current_round == 14)
-            && !self.harvest_done
-        {
-            return false;
-        }
-        true
+        !self.is_harvest()
     }
 
     // When all rounds in the previous stage are played - it is time for harvest
     #[must_use]
     pub fn is_harvest(&self) -> bool {
-        (self.current_round == 4
-            || self.current_round == 7
-            || self.current_round == 9
-            || self.current_round == 11
-            || self.current_round == 13
-            || self.current_round == 14)
-            && !self.harvest_done
+        self.ruleset.is_harvest_round(self.current_round) && !self.harvest_done
     }
 
     pub fn harvest_fields(&mut self) {
@@ -204,7 +304,7 @@ impl State {
 
     #[must_use]
     pub fn can_grow_family_with_room(&self, player_idx: usize) -> bool {
-        self.family_members(player_idx) < MAX_FAMILY_MEMBERS
+        self.family_members(player_idx) < self.ruleset.max_family_members
             && self.player_flags(player_idx)[HasRoomToGrow.index()]
     }
 
@@ -230,6 +330,10 @@ impl State {
         self.player_types[player_idx]
     }
 
+    pub fn set_player_type(&mut self, player_idx: usize, player_type: PlayerType) {
+        self.player_types[player_idx] = player_type;
+    }
+
     /// # Panics
     /// Will panic if partial comparison fails
     #[must_use]
@@ -283,62 +387,156 @@ impl State {
     }
 
     pub fn reset_for_next_round(&mut self) {
-        self.player_quantities.iter_mut().for_each(|p| {
-            p[MembersPlacedThisRound.index()] = 0;
-            p[AdultMembers.index()] += p[Children.index()];
-            p[Children.index()] = 0;
-        });
+        let num_players = self.num_players;
+        self.player_quantities
+            .iter_mut()
+            .take(num_players)
+            .for_each(|p| {
+                p[MembersPlacedThisRound.index()] = 0;
+                p[AdultMembers.index()] += p[Children.index()];
+                p[Children.index()] = 0;
+            });
+
+        self.player_flags
+            .iter_mut()
+            .take(num_players)
+            .for_each(|p| {
+                p[HarvestPaid.index()] = false;
+                p[BeforeRoundStart.index()] = true;
+            });
+    }
+
+    /// Round cards that may be revealed at the start of the next round, all equally likely
+    #[must_use]
+    pub fn unrevealed_round_cards(&self) -> Vec<usize> {
+        self.round_card_candidates(self.current_round + 1)
+    }
 
-        self.player_flags.iter_mut().for_each(|p| {
-            p[HarvestPaid.index()] = false;
-            p[BeforeRoundStart.index()] = true;
-        });
+    /// Round cards that can still be revealed for `round`
+    #[must_use]
+    pub fn round_card_candidates(&self, round: usize) -> Vec<usize> {
+        round_card_candidates(&self.ruleset.action_spaces, &self.action_spaces, round)
     }
 
+    /// Puts `card_idx` in the slot of `round` in the sequence of action spaces
+    /// # Panics
+    /// If `card_idx` cannot be revealed for `round`
+    pub fn reveal_round_card(&mut self, round: usize, card_idx: usize) {
+        reveal_round_card(
+            &self.ruleset.action_spaces,
+            &mut self.action_spaces,
+            round,
+            card_idx,
+        );
+    }
+
+    /// For each future round, the round cards it may reveal with their probabilities
+    #[must_use]
+    pub fn upcoming_round_cards(&self) -> Vec<(usize, Vec<(usize, f64)>)> {
+        upcoming_round_cards(
+            &self.ruleset.action_spaces,
+            &self.action_spaces,
+            self.current_round,
+        )
+    }
+
+    /// Whether the next action is decided by chance rather than by a player
+    #[must_use]
+    pub fn is_chance_node(&self) -> bool {
+        matches!(self.last_action, Action::StartRound)
+    }
+
+    /// The possible outcomes of a chance node and their probabilities, empty if not a chance node
+    #[must_use]
+    pub fn chance_outcomes(&self) -> Vec<(Action, f64)> {
+        if !self.is_chance_node() {
+            return Vec::new();
+        }
+        let cards = self.unrevealed_round_cards();
+        #[allow(clippy::cast_precision_loss)]
+        let probability = 1.0 / cards.len() as f64;
+        cards
+            .into_iter()
+            .map(|idx| (Action::RevealRoundCard(idx), probability))
+            .collect()
+    }
+
+    /// Draws the outcome of a chance node with the game's random number generator
+    /// # Panics
+    /// Will panic if the state is not a chance node
+    pub fn sample_chance(&mut self) -> Action {
+        let outcomes = self.chance_outcomes();
+        assert!(!outcomes.is_empty());
+        let idx = self.rng.gen_range(0..outcomes.len());
+        outcomes[idx].0.clone()
+    }
+
+    /// Starts the next round with `card_idx` as its newly revealed round card
     /// # Panics
     ///
     /// Will panic if a new round cannot be initialized
-    pub fn init_new_round(&mut self) {
+    pub fn init_new_round(&mut self, card_idx: usize) {
         assert!(self.can_init_new_round());
         self.current_round += 1;
-        randomize_action_spaces(&mut self.action_spaces, self.current_round);
+        self.reveal_round_card(self.current_round, card_idx);
 
         // Reset workers
         self.reset_for_next_round();
         self.people_placed_this_round = 0;
 
-        self.occupied = [None; NUM_ACTION_SPACES];
+        self.occupied.fill(None);
 
         // Update accumulation spaces
-        for i in 0..OPEN_SPACES + self.current_round {
+        let ruleset = Arc::clone(&self.ruleset);
+        for i in 0..ruleset.num_open_spaces() + self.current_round {
             let idx = self.action_spaces[i];
-            accumulate(idx, &mut self.accumulated_resources[idx]);
+            ruleset.action_spaces[idx].accumulate(&mut self.accumulated_resources[idx]);
         }
 
-        // Delete old events
-        self.start_round_events
-            .retain(|e| e.round >= self.current_round);
-
         // Reset start player
         self.current_player_idx = self.starting_player_idx;
 
-        // Look for start round events
-        let start_round_events = self.start_round_events.clone();
-        for event in start_round_events {
-            for i in 0..self.num_players {
-                if event.round == self.current_round && event.player_idx == i {
-                    (event.func)(self.player_quantities_mut(i));
-                }
-            }
+        // Deliver goods scheduled for this round
+        let (due, later): (Vec<ScheduledGoods>, Vec<ScheduledGoods>) = self
+            .scheduled_goods
+            .drain(..)
+            .partition(|g| g.round == self.current_round);
+        self.scheduled_goods = later;
+        for goods in due {
+            take_resource(
+                &goods.resources,
+                self.player_quantities_mut(goods.player_idx),
+            );
         }
 
         // Reset harvest flag
         self.harvest_done = false;
     }
 
-    pub fn add_new_field(&mut self, idx: &usize) {
-        self.current_farm_mut().add_field(*idx);
+    /// Collects the goods of an action space for the current player
+    pub fn use_action_space(&mut self, idx: usize) {
+        let ruleset = Arc::clone(&self.ruleset);
+        let space = &ruleset.action_spaces[idx];
+        let accumulated = self.accumulated_resources[idx];
+        take_resources(self.current_player_quantities_mut(), &accumulated);
+        take_resources(self.current_player_quantities_mut(), &space.fixed);
+        self.accumulated_resources[idx] = [0; NUM_RESOURCES];
+
+        if space.gives_animals() {
+            self.accommodate_animals(false);
+        }
+
+        if space.follow_up == FollowUp::StartingPlayer {
+            self.starting_player_idx = self.current_player_idx;
+        }
+    }
+
+    /// # Errors
+    /// If there is no empty space at `idx`
+    pub fn add_new_field(&mut self, idx: &usize) -> Result<(), RuleError> {
+        self.current_farm_mut().add_field(*idx)?;
         remove_farmyard_idx(&mut self.fence_options_cache[self.current_player_idx], *idx);
+        Ok(())
     }
 
     #[must_use]
@@ -420,15 +618,18 @@ impl State {
     }
 
     /// Builds a single room
-    /// # Panics
-    /// Will panic if the player cannot build a room
-    pub fn build_room(&mut self, idx: &usize) {
-        assert!(self.can_build_room());
+    /// # Errors
+    /// If the player cannot pay for the room or there is no empty space at `idx`
+    pub fn build_room(&mut self, idx: &usize) -> Result<(), RuleError> {
         // By default Rooms cost 5 of the corresponding building resource (as the material of the house and 2 Reed)
-        let room_material_idx = self.room_material_idx(self.current_player_idx);
-        self.current_player_quantities_mut()[room_material_idx] -= 5;
-        self.current_player_quantities_mut()[Reed.index()] -= 2;
-        self.current_farm_mut().build_room(*idx);
+        let mut room_cost = new_res();
+        room_cost[self.room_material_idx(self.current_player_idx)] = 5;
+        room_cost[Reed.index()] = 2;
+        if !can_pay_for_resource(&room_cost, self.current_player_quantities()) {
+            return Err(RuleError::NotEnoughResources(room_cost));
+        }
+        self.current_farm_mut().build_room(*idx)?;
+        pay_for_resource(&room_cost, self.current_player_quantities_mut())?;
         remove_farmyard_idx(&mut self.fence_options_cache[self.current_player_idx], *idx);
 
         // Increment player quantities
@@ -439,6 +640,7 @@ impl State {
         if rooms > self.family_members(self.current_player_idx) {
             self.current_player_flags_mut()[HasRoomToGrow.index()] = true;
         }
+        Ok(())
     }
 
     #[must_use]
@@ -452,16 +654,28 @@ impl State {
     #[must_use]
     pub fn can_build_stable(&self) -> bool {
         self.current_player_quantities()[Wood.index()] >= 2
-            && self.current_farm().can_build_stable()
+            && self
+                .current_farm()
+                .can_build_stable(self.ruleset.max_stables)
     }
 
     /// Builds a single stable
-    /// # Panics
-    /// Will panic if the player cannot build a stable
-    pub fn build_stable(&mut self, idx: &usize) {
-        assert!(self.can_build_stable());
-        self.current_player_quantities_mut()[Wood.index()] -= 2;
-        self.current_farm_mut().build_stable(*idx);
+    /// # Errors
+    /// If the player has no stables left to build, cannot pay for the stable or there is no space for one at `idx`
+    pub fn build_stable(&mut self, idx: &usize) -> Result<(), RuleError> {
+        if !self
+            .current_farm()
+            .can_build_stable(self.ruleset.max_stables)
+        {
+            return Err(RuleError::NoStablesLeft);
+        }
+        let mut stable_cost = new_res();
+        stable_cost[Wood.index()] = 2;
+        if !can_pay_for_resource(&stable_cost, self.current_player_quantities()) {
+            return Err(RuleError::NotEnoughResources(stable_cost));
+        }
+        self.current_farm_mut().build_stable(*idx)?;
+        pay_for_resource(&stable_cost, self.current_player_quantities_mut())
     }
 
     #[must_use]
@@ -472,82 +686,125 @@ impl State {
         None
     }
 
+    /// Renovations the current player can afford
+    #[must_use]
+    pub fn renovation_options(&self) -> Vec<Renovation> {
+        renovation_options(
+            self.room_material_idx(self.current_player_idx),
+            self.current_player_quantities()[Rooms.index()],
+            self.current_player_cards(),
+        )
+        .into_iter()
+        .filter(|reno| can_pay_for_resource(&reno.cost, self.current_player_quantities()))
+        .collect()
+    }
+
     #[must_use]
     pub fn can_renovate(&self) -> bool {
-        let reno_material = self.renovation_material_idx();
-        if let Some(reno_material) = reno_material {
-            return self.current_player_quantities()[Reed.index()] >= 1
-                && (self.current_player_quantities()[reno_material]
-                    >= self.current_player_quantities()[Rooms.index()]);
-        }
-        false
+        !self.renovation_options().is_empty()
     }
 
-    fn renovation_material_idx(&self) -> Option<usize> {
-        if self.current_player_flags()[WoodHouse.index()] {
-            Some(Clay.index())
-        } else if self.current_player_flags()[ClayHouse.index()] {
-            Some(Stone.index())
-        } else {
-            None
-        }
+    /// # Errors
+    /// If the player cannot pay for the renovation
+    /// # Panics
+    /// Will panic if the renovation does not apply to the house of the player
+    pub fn renovate(&mut self, renovation: &Renovation) -> Result<(), RuleError> {
+        assert!(renovation_options(
+            self.room_material_idx(self.current_player_idx),
+            self.current_player_quantities()[Rooms.index()],
+            self.current_player_cards(),
+        )
+        .contains(renovation));
+        pay_for_resource(&renovation.cost, self.current_player_quantities_mut())?;
+        self.set_house_material(self.current_player_idx, renovation.target);
+        Ok(())
     }
 
     /// # Panics
-    /// Will panic if the player cannot renovate
-    pub fn renovate(&mut self) {
-        assert!(self.can_renovate());
-        // TODO for cards like Conservator this must be implemented in a more general way
-        let renovation_material_idx = self.renovation_material_idx();
-
-        if let Some(renovation_material_idx) = renovation_material_idx {
-            assert!(
-                self.current_player_quantities()[renovation_material_idx]
-                    >= self.current_player_quantities()[Rooms.index()]
-            );
-            assert!(self.current_player_quantities()[Reed.index()] >= 1);
-            self.current_player_quantities_mut()[renovation_material_idx] -=
-                self.current_player_quantities()[Rooms.index()];
-            self.current_player_quantities_mut()[Reed.index()] -= 1;
-
-            if renovation_material_idx == Clay.index() {
-                self.current_player_flags_mut()[WoodHouse.index()] = false;
-                self.current_player_flags_mut()[ClayHouse.index()] = true;
-                self.current_player_flags_mut()[StoneHouse.index()] = false;
-            } else if renovation_material_idx == Stone.index() {
-                self.current_player_flags_mut()[WoodHouse.index()] = false;
-                self.current_player_flags_mut()[ClayHouse.index()] = false;
-                self.current_player_flags_mut()[StoneHouse.index()] = true;
+    /// Will panic if `material_idx` is not Wood, Clay or Stone
+    pub fn set_house_material(&mut self, player_idx: usize, material_idx: usize) {
+        assert!([Wood.index(), Clay.index(), Stone.index()].contains(&material_idx));
+        let flags = &mut self.player_flags[player_idx];
+        flags[WoodHouse.index()] = material_idx == Wood.index();
+        flags[ClayHouse.index()] = material_idx == Clay.index();
+        flags[StoneHouse.index()] = material_idx == Stone.index();
+    }
+
+    /// # Errors
+    /// If the player cannot pay for the improvement
+    pub fn build_major(
+        &mut self,
+        major_idx: usize,
+        return_fireplace: bool,
+    ) -> Result<(), RuleError> {
+        if return_fireplace
+            && (major_idx == CookingHearth1.index() || major_idx == CookingHearth2.index())
+        {
+            self.replace_fireplace_with_cooking_hearth();
+        } else {
+            pay_for_resource(&cost(major_idx), self.current_player_quantities_mut())?;
+            self.current_player_cards_mut()[major_idx] = true;
+
+            for (i, resources) in future_goods(major_idx).iter().enumerate() {
+                self.schedule_goods(
+                    self.current_player_idx,
+                    self.current_round + i + 1,
+                    resources,
+                );
             }
         }
-    }
 
-    pub fn build_major(&mut self, major_idx: usize, return_fireplace: bool) {
         if COOKING_IMPROVEMENTS_INDICES.contains(&major_idx) {
             self.current_player_flags_mut()[HasCookingImprovement.index()] = true;
         }
+        Ok(())
+    }
 
-        if return_fireplace
-            && (major_idx == CookingHearth1.index() || major_idx == CookingHearth2.index())
+    /// Promise `resources` to a player at the start of `round`. Goods for rounds that will not be played are dropped.
+    pub fn schedule_goods(&mut self, player_idx: usize, round: usize, resources: &Resources) {
+        if round > self.ruleset.num_rounds() {
+            return;
+        }
+
+        if let Some(existing) = self
+            .scheduled_goods
+            .iter_mut()
+            .find(|g| g.round == round && g.player_idx == player_idx)
         {
-            self.replace_fireplace_with_cooking_hearth();
-        } else {
-            pay_for_resource(&cost(major_idx), self.current_player_quantities_mut());
-            self.current_player_cards_mut()[major_idx] = true;
+            for (i, n) in resources.iter().enumerate() {
+                existing.resources[i] += n;
+            }
+            return;
+        }
 
-            if major_idx == Well.index() {
-                let func = |res: &mut Quantities| {
-                    res[Food.index()] += 1;
-                };
-                for i in 1..=5 {
-                    self.start_round_events.push(Event {
-                        round: self.current_round + i,
-                        player_idx: self.current_player_idx,
-                        func,
-                    });
-                }
+        self.scheduled_goods.push(ScheduledGoods {
+            round,
+            player_idx,
+            resources: *resources,
+        });
+        self.scheduled_goods
+            .sort_by_key(|g| (g.round, g.player_idx));
+    }
+
+    /// Goods still to come for a player, in round order
+    #[must_use]
+    pub fn upcoming_goods(&self, player_idx: usize) -> Vec<&ScheduledGoods> {
+        self.scheduled_goods
+            .iter()
+            .filter(|g| g.player_idx == player_idx)
+            .collect()
+    }
+
+    /// Total goods still to come for a player
+    #[must_use]
+    pub fn total_upcoming_goods(&self, player_idx: usize) -> Resources {
+        let mut ret = [0; NUM_RESOURCES];
+        for goods in self.upcoming_goods(player_idx) {
+            for (i, n) in goods.resources.iter().enumerate() {
+                ret[i] += n;
             }
         }
+        ret
     }
 
     #[must_use]
@@ -555,6 +812,7 @@ impl State {
         self.current_farm().fencing_options(
             &self.fence_options_cache[self.current_player_idx],
             self.current_player_quantities()[Wood.index()],
+            self.ruleset.max_fences,
         )
     }
 
@@ -577,6 +835,7 @@ impl State {
             .fencing_options(
                 &self.fence_options_cache[self.current_player_idx],
                 self.current_player_quantities()[Wood.index()],
+                self.ruleset.max_fences,
             )
             .is_empty()
     }
@@ -603,6 +862,32 @@ impl State {
             > 0
     }
 
+    /// Every way the current player can bake, including allocations that yield less food than the best one
+    #[must_use]
+    pub fn baking_allocations(&self) -> Vec<BakingAllocation> {
+        if !self.can_bake_bread(self.current_player_idx) {
+            return Vec::new();
+        }
+        baking_allocations(
+            self.current_player_cards(),
+            self.current_player_quantities()[Grain.index()],
+        )
+    }
+
+    /// The best allocation of the grain baked by `allocation`, None if the current player cannot bake `allocation`
+    #[must_use]
+    pub fn best_baking_allocation(
+        &self,
+        allocation: &BakingAllocation,
+    ) -> Option<BakingAllocation> {
+        let mut sorted = allocation.clone();
+        sorted.sort_unstable();
+        if !self.baking_allocations().contains(&sorted) {
+            return None;
+        }
+        best_baking_allocation(self.current_player_cards(), allocation_grain(allocation))
+    }
+
     #[must_use]
     pub fn can_bake_bread(&self, player_idx: usize) -> bool {
         // Check if any of the baking improvements are present
@@ -613,12 +898,59 @@ impl State {
             && self.current_player_quantities()[Grain.index()] > 0
     }
 
-    pub fn sow_field(&mut self, seed: &Seed) {
-        self.current_farm_mut().sow_field(seed);
-        match seed {
-            Seed::Grain => self.current_player_quantities_mut()[Grain.index()] -= 1,
-            Seed::Vegetable => self.current_player_quantities_mut()[Vegetable.index()] -= 1,
+    /// Sows every field in the plan, paying one grain or vegetable per field
+    /// # Errors
+    /// If a space in the plan is not an empty field or the player has too few seeds, nothing is sown then
+    pub fn sow(&mut self, plan: &SowingPlan) -> Result<(), RuleError> {
+        let mut seeds = new_res();
+        let mut farm = self.current_farm().clone();
+        for (idx, seed) in plan {
+            let amount = sow_amount(*seed, self.current_player_cards());
+            farm.sow_field(*idx, *seed, amount)?;
+            match seed {
+                Seed::Grain => seeds[Grain.index()] += 1,
+                Seed::Vegetable => seeds[Vegetable.index()] += 1,
+            }
+        }
+        pay_for_resource(&seeds, self.current_player_quantities_mut())?;
+        *self.current_farm_mut() = farm;
+        Ok(())
+    }
+
+    /// Every way the current player can sow, field by field
+    #[must_use]
+    pub fn sowing_plans(&self) -> Vec<SowingPlan> {
+        self.current_farm().sowing_plans(
+            self.current_player_quantities()[Grain.index()],
+            self.current_player_quantities()[Vegetable.index()],
+        )
+    }
+
+    /// The ways the current player can sow that lead to different farms, one for each (grain, vegetable) split
+    #[must_use]
+    pub fn distinct_sowing_plans(&self) -> Vec<SowingPlan> {
+        self.current_farm().distinct_sowing_plans(
+            self.current_player_quantities()[Grain.index()],
+            self.current_player_quantities()[Vegetable.index()],
+        )
+    }
+
+    /// The plan among `distinct_sowing_plans` that sows the same crops as `plan`, None if `plan` cannot be sown
+    #[must_use]
+    pub fn distinct_sowing_plan(&self, plan: &SowingPlan) -> Option<SowingPlan> {
+        let empty_fields = self.current_farm().empty_field_indices();
+        let mut fields: Vec<usize> = plan.iter().map(|(idx, _)| *idx).collect();
+        fields.sort_unstable();
+        fields.dedup();
+        if fields.len() != plan.len() || fields.iter().any(|idx| !empty_fields.contains(idx)) {
+            return None;
         }
+        let num_grain = plan.iter().filter(|(_, seed)| *seed == Seed::Grain).count();
+        let num_veg = plan.len() - num_grain;
+        (num_grain + num_veg > 0
+            && num_grain <= self.current_player_quantities()[Grain.index()]
+            && num_veg <= self.current_player_quantities()[Vegetable.index()])
+        .then(|| self.current_farm().distinct_sowing_plan(num_grain, num_veg))
     }
 
     #[must_use]
@@ -628,48 +960,19 @@ impl State {
             && self.current_farm().can_sow()
     }
 
+    /// Bakes the allocated grain on each of the improvements
     /// # Panics
-    /// Will panic if the player cannot bake
-    pub fn bake_bread(&mut self, num_grain_to_bake: usize) {
+    /// Will panic if the player cannot bake, does not own an allocated improvement or exceeds its capacity
+    pub fn bake_bread(&mut self, allocation: &BakingAllocation) {
         assert!(self.can_bake_bread(self.current_player_idx));
-        let mut num_grain_to_bake = num_grain_to_bake;
-        while num_grain_to_bake > 0 {
-            if self.current_player_quantities()[Grain.index()] == 0 {
-                break;
-            }
-
-            if self.current_player_cards()[ClayOven.index()]
-                && !self.current_player_flags()[BakedOnceWithClayOven.index()]
-            {
-                self.current_player_quantities_mut()[Food.index()] += 5;
-                self.current_player_flags_mut()[BakedOnceWithClayOven.index()] = true;
-            } else if self.current_player_cards()[StoneOven.index()]
-                && !self.current_player_flags()[BakedOnceWithStoneOven.index()]
-            {
-                self.current_player_quantities_mut()[Food.index()] += 4;
-                self.current_player_flags_mut()[BakedOnceWithStoneOven.index()] = true;
-            } else if self.current_player_cards()[StoneOven.index()]
-                && !self.current_player_flags()[BakedTwiceWithStoneOven.index()]
-            {
-                self.current_player_quantities_mut()[Food.index()] += 4;
-                self.current_player_flags_mut()[BakedTwiceWithStoneOven.index()] = true;
-            } else if self.current_player_cards()[CookingHearth1.index()]
-                || self.current_player_cards()[CookingHearth2.index()]
-            {
-                self.current_player_quantities_mut()[Food.index()] += 3;
-            } else if self.current_player_cards()[Fireplace1.index()]
-                || self.current_player_cards()[Fireplace2.index()]
-            {
-                self.current_player_quantities_mut()[Food.index()] += 2;
-            }
-
-            self.current_player_quantities_mut()[Grain.index()] -= 1;
-            num_grain_to_bake -= 1;
+        for (major_idx, num_grain) in allocation {
+            assert!(self.current_player_cards()[*major_idx]);
+            assert!(baking_capacity(*major_idx).is_none_or(|cap| *num_grain <= cap));
+            assert!(self.current_player_quantities()[Grain.index()] >= *num_grain);
+            self.current_player_quantities_mut()[Grain.index()] -= num_grain;
+            self.current_player_quantities_mut()[Food.index()] +=
+                baking_food(*major_idx, *num_grain);
         }
-
-        self.current_player_flags_mut()[BakedOnceWithClayOven.index()] = false;
-        self.current_player_flags_mut()[BakedOnceWithStoneOven.index()] = false;
-        self.current_player_flags_mut()[BakedTwiceWithStoneOven.index()] = false;
     }
 
     #[must_use]
@@ -683,12 +986,25 @@ impl State {
         self.current_player_flags_mut()[BeforeRoundStart.index()] = false;
     }
 
-    pub fn pay_food_or_beg(&mut self) {
+    #[must_use]
+    pub fn feeding_plans(&self) -> Vec<FeedingPlan> {
+        feeding_plans(
+            self.food_required(),
+            self.current_player_quantities(),
+            self.current_player_cards(),
+        )
+    }
+
+    /// Converts goods according to the plan, then feeds the family - begging for any missing food
+    pub fn pay_food_or_beg(&mut self, plan: &FeedingPlan) {
+        for (res_ex, times) in &plan.exchanges {
+            for _ in 0..*times {
+                self.use_exchange(res_ex);
+            }
+        }
+
         let food_required = self.food_required();
         self.current_player_flags_mut()[HarvestPaid.index()] = true;
-        self.current_player_flags_mut()[UsedJoinery.index()] = false;
-        self.current_player_flags_mut()[UsedPottery.index()] = false;
-        self.current_player_flags_mut()[UsedBasketmakersWorkshop.index()] = false;
 
         if food_required > self.current_player_quantities()[Food.index()] {
             self.current_player_quantities_mut()[BeggingTokens.index()] +=
@@ -812,7 +1128,7 @@ impl State {
 
     #[must_use]
     pub fn can_grow_family_without_room(&self, player_idx: usize) -> bool {
-        self.family_members(player_idx) < MAX_FAMILY_MEMBERS
+        self.family_members(player_idx) < self.ruleset.max_family_members
     }
 
     pub fn grow_family(&mut self, with_room: bool) {
@@ -874,22 +1190,29 @@ impl State {
     pub fn format(&self) -> String {
         let mut ret: String = String::new();
 
-        for i in (0..NUM_ACTION_SPACES).take(OPEN_SPACES + self.current_round) {
-            let idx = self.action_spaces[i];
+        for &idx in self.revealed_action_spaces() {
+            let space = self.action_space(idx);
             if let Some(player_idx) = self.occupied[idx] {
-                ret.push_str(&format!(
-                    "\n[{}] {}",
-                    player_idx + 1,
-                    ACTION_SPACE_NAMES[idx]
-                ));
+                ret.push_str(&format!("\n[{}] {}", player_idx + 1, space.name));
             } else {
-                ret.push_str(&format!("\n[-] {}", ACTION_SPACE_NAMES[idx]));
-                if ACCUMULATION_SPACE_INDICES.contains(&idx) {
+                ret.push_str(&format!("\n[-] {}", space.name));
+                if space.is_accumulation() {
                     ret.push_str(&format_resources(&self.accumulated_resources[idx]));
                 }
             }
         }
 
+        if let Some((round, odds)) = self.upcoming_round_cards().first() {
+            ret.push_str(&format!("\n\n=== Round {round} ===\n"));
+            for (idx, probability) in odds {
+                ret.push_str(&format!(
+                    "\n{:3.0}% {}",
+                    probability * 100.0,
+                    self.action_space(*idx).name
+                ));
+            }
+        }
+
         ret.push_str("\n\n=== Cards ===\n");
 
         for (card_idx, card_name) in CARD_NAMES.iter().enumerate() {
@@ -983,6 +1306,10 @@ impl State {
         &mut self.farms[self.current_player_idx]
     }
 
+    pub fn player_farm_mut(&mut self, player_idx: usize) -> &mut Farm {
+        &mut self.farms[player_idx]
+    }
+
     #[must_use]
     pub fn player_farm(&self, player_idx: usize) -> &Farm {
         &self.farms[player_idx]
@@ -1032,7 +1359,7 @@ impl State {
                 total_weight += weight;
             }
 
-            let mut action_idx_weight = rand::thread_rng().gen_range(0.0..total_weight);
+            let mut action_idx_weight = self.rng.gen_range(0.0..total_weight);
             for (action, weight) in &choices {
                 if action_idx_weight < *weight {
                     action.apply_choice(self);
@@ -1065,7 +1392,7 @@ impl State {
             d += 1;
 
             // Chose a random action
-            let action_idx = rand::thread_rng().gen_range(0..choices.len());
+            let action_idx = self.rng.gen_range(0..choices.len());
             choices[action_idx].0.apply_choice(self);
         }
     }
@@ -1077,6 +1404,8 @@ mod tests {
 
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
+    use crate::agricola::card::{best_baking_allocation, ClayOven, Conservator, Well};
+    use crate::agricola::farm::ROOM_INDICES;
 
     #[test]
     fn test_can_use_farm_expansion() {
@@ -1100,8 +1429,8 @@ mod tests {
         assert!(state.can_build_stable());
 
         // Build two stable
-        state.build_stable(&0);
-        state.build_stable(&1);
+        state.build_stable(&0).unwrap();
+        state.build_stable(&1).unwrap();
 
         // Now there isn't enough wood to build another stable
         assert!(!state.can_build_stable());
@@ -1112,10 +1441,284 @@ mod tests {
         assert!(state.can_build_stable());
 
         // Build 2 more stables
-        state.build_stable(&2);
-        state.build_stable(&3);
+        state.build_stable(&2).unwrap();
+        state.build_stable(&3).unwrap();
 
         // Now there still is enough wood to build another stable but MAX_STABLES is reached
         assert!(!state.can_build_stable());
     }
+
+    #[test]
+    fn test_sowing_plans() {
+        let mut state = State::new(&[PlayerType::Human]).unwrap();
+        state.add_new_field(&0).unwrap();
+        state.add_new_field(&1).unwrap();
+        state.current_player_quantities_mut()[Grain.index()] = 1;
+        state.current_player_quantities_mut()[Vegetable.index()] = 2;
+
+        // Grain / Vegetable splits : 0/1, 0/2, 1/0, 1/1
+        assert_eq!(state.distinct_sowing_plans().len(), 4);
+        // Field by field : G-, -G, V-, -V, VV, GV, VG
+        let plans = state.sowing_plans();
+        assert_eq!(plans.len(), 7);
+        for plan in &plans {
+            let distinct = state.distinct_sowing_plan(plan).unwrap();
+            assert!(state.distinct_sowing_plans().contains(&distinct));
+        }
+        assert!(state
+            .distinct_sowing_plan(&vec![(0, Seed::Grain), (1, Seed::Grain)])
+            .is_none());
+        assert!(state
+            .distinct_sowing_plan(&vec![(2, Seed::Grain)])
+            .is_none());
+
+        state
+            .sow(&vec![(0, Seed::Grain), (1, Seed::Vegetable)])
+            .unwrap();
+        assert_eq!(
+            state.grain_and_veg_on_fields(state.current_player_idx),
+            (3, 2)
+        );
+        assert_eq!(state.current_player_quantities()[Grain.index()], 0);
+        assert_eq!(state.current_player_quantities()[Vegetable.index()], 1);
+        assert!(!state.can_sow());
+    }
+
+    #[test]
+    fn test_best_baking_allocation() {
+        let mut state = State::new(&[PlayerType::Human]).unwrap();
+        state.current_player_cards_mut()[Fireplace1.index()] = true;
+        state.current_player_cards_mut()[ClayOven.index()] = true;
+        state.current_player_quantities_mut()[Grain.index()] = 3;
+
+        let allocation = best_baking_allocation(state.current_player_cards(), 3).unwrap();
+        assert_eq!(
+            allocation,
+            vec![(Fireplace1.index(), 2), (ClayOven.index(), 1)]
+        );
+
+        // Other splits are not offered but stand in for the best split of the same grain
+        assert_eq!(state.baking_allocations().len(), 6);
+        assert_eq!(
+            state.best_baking_allocation(&vec![(Fireplace1.index(), 1)]),
+            Some(vec![(ClayOven.index(), 1)])
+        );
+        assert!(state
+            .best_baking_allocation(&vec![(ClayOven.index(), 2)])
+            .is_none());
+
+        let food = state.current_player_quantities()[Food.index()];
+        state.bake_bread(&allocation);
+        assert_eq!(state.current_player_quantities()[Food.index()], food + 9);
+        assert_eq!(state.current_player_quantities()[Grain.index()], 0);
+
+        // Ovens alone cannot bake more than their capacity
+        let mut cards = [false; NUM_CARDS];
+        cards[ClayOven.index()] = true;
+        assert!(best_baking_allocation(&cards, 2).is_none());
+    }
+
+    #[test]
+    fn test_well_schedules_food() {
+        let mut state = State::new(&[PlayerType::Human]).unwrap();
+        state.init_new_round(state.unrevealed_round_cards()[0]);
+        let player_idx = state.current_player_idx;
+        state.current_player_quantities_mut()[Wood.index()] = 1;
+        state.current_player_quantities_mut()[Stone.index()] = 3;
+        state.build_major(Well.index(), false).unwrap();
+
+        let upcoming = state.upcoming_goods(player_idx);
+        assert_eq!(upcoming.len(), 5);
+        assert_eq!(upcoming[0].round, 2);
+        assert_eq!(state.total_upcoming_goods(player_idx)[Food.index()], 5);
+
+        // Goods on the same round space are merged
+        let mut res = [0; NUM_RESOURCES];
+        res[Wood.index()] = 2;
+        state.schedule_goods(player_idx, 2, &res);
+        assert_eq!(state.upcoming_goods(player_idx).len(), 5);
+
+        let food = state.current_player_quantities()[Food.index()];
+        state.init_new_round(state.unrevealed_round_cards()[0]);
+        assert_eq!(state.player_quantities(player_idx)[Food.index()], food + 1);
+        assert_eq!(state.player_quantities(player_idx)[Wood.index()], 2);
+        assert_eq!(state.upcoming_goods(player_idx).len(), 4);
+    }
+
+    #[test]
+    fn test_conservator_skips_clay() {
+        let mut state = State::new(&[PlayerType::Human]).unwrap();
+        state.current_player_quantities_mut()[Clay.index()] = 2;
+        state.current_player_quantities_mut()[Stone.index()] = 2;
+        state.current_player_quantities_mut()[Reed.index()] = 1;
+
+        let options = state.renovation_options();
+        assert_eq!(options.len(), 1);
+        assert_eq!(options[0].target, Clay.index());
+
+        state.current_player_cards_mut()[Conservator.index()] = true;
+        let options = state.renovation_options();
+        assert_eq!(options.len(), 2);
+
+        let to_stone = options
+            .into_iter()
+            .find(|reno| reno.target == Stone.index())
+            .unwrap();
+        state.renovate(&to_stone).unwrap();
+        assert_eq!(
+            state.room_material_idx(state.current_player_idx),
+            Stone.index()
+        );
+        assert!(!state.current_player_flags()[WoodHouse.index()]);
+        assert_eq!(state.current_player_quantities()[Stone.index()], 0);
+        assert_eq!(state.current_player_quantities()[Clay.index()], 2);
+        assert!(!state.can_renovate());
+    }
+
+    #[test]
+    fn test_same_seed_same_game() {
+        let players = [PlayerType::MctsAI, PlayerType::MctsAI];
+        let mut state1 = State::with_seed(&players, 42).unwrap();
+        let mut state2 = State::with_seed(&players, 42).unwrap();
+        state1.play_weighted_random(None);
+        state2.play_weighted_random(None);
+        assert_eq!(state1.get_hash(), state2.get_hash());
+        assert_eq!(state1.action_spaces, state2.action_spaces);
+        assert_eq!(state1.scores(), state2.scores());
+    }
+
+    #[test]
+    fn test_round_card_chance_outcomes() {
+        let mut state = State::with_seed(&[PlayerType::Human], 3).unwrap();
+        assert!(!state.is_chance_node());
+        Action::StartGame.apply_choice(&mut state);
+        Action::StartRound.apply_choice(&mut state);
+        assert!(state.is_chance_node());
+
+        // Any of the four stage 1 cards can open the game
+        let outcomes = state.chance_outcomes();
+        assert_eq!(outcomes.len(), 4);
+        assert!(outcomes.iter().all(|(_, p)| (p - 0.25).abs() < EPSILON));
+
+        let outcome = state.sample_chance();
+        outcome.apply_choice(&mut state);
+        assert!(!state.is_chance_node());
+        assert_eq!(state.current_round, 1);
+        assert_eq!(state.unrevealed_round_cards().len(), 3);
+    }
+
+    #[test]
+    fn test_upcoming_round_cards() {
+        let mut state = State::with_seed(&[PlayerType::Human], 5).unwrap();
+        let upcoming = state.upcoming_round_cards();
+        assert_eq!(upcoming.len(), 14);
+        assert_eq!(upcoming[0].1.len(), 4);
+        assert_eq!(upcoming[4].1.len(), 3);
+        assert_eq!(upcoming[13].1.len(), 1);
+
+        Action::StartGame.apply_choice(&mut state);
+        Action::StartRound.apply_choice(&mut state);
+        let outcome = state.sample_chance();
+        outcome.apply_choice(&mut state);
+
+        // The revealed card cannot appear again, the rest of stage 1 shares the odds
+        let upcoming = state.upcoming_round_cards();
+        assert_eq!(upcoming[0].0, 2);
+        assert_eq!(upcoming[0].1.len(), 3);
+        assert!(upcoming[0].1.iter().all(|(idx, p)| *idx
+            != state.action_spaces[state.ruleset().num_open_spaces()]
+            && (p - 1.0 / 3.0).abs() < EPSILON));
+    }
+
+    #[test]
+    fn test_try_apply() {
+        let mut state = State::with_seed(&[PlayerType::Human; 2], 11).unwrap();
+        let hash = state.get_hash();
+        assert_eq!(
+            state.try_apply(&Action::EndTurn),
+            Err(RuleError::IllegalAction(Action::EndTurn))
+        );
+        assert_eq!(state.get_hash(), hash);
+        assert_eq!(state.try_apply(&Action::StartRound), Ok(()));
+
+        state.play_random(None);
+        assert_eq!(
+            state.try_apply(&Action::StartRound),
+            Err(RuleError::GameOver)
+        );
+    }
+
+    #[test]
+    fn test_rule_errors() {
+        let mut state = State::with_seed(&[PlayerType::Human; 2], 11).unwrap();
+        let to_place_worker = |state: &mut State| {
+            while state.last_action != Action::PlaceWorker {
+                let choices = Action::next_choices(state);
+                choices[0].0.apply_choice(state);
+            }
+        };
+        to_place_worker(&mut state);
+        let farm_expansion = (0..state.ruleset().num_action_spaces())
+            .find(|idx| state.action_space(*idx).name == "Farm Expansion")
+            .unwrap();
+        state.current_player_quantities_mut()[Wood.index()] = 5;
+        state.current_player_quantities_mut()[Reed.index()] = 2;
+        state.try_apply(&Action::UseSpace(farm_expansion)).unwrap();
+
+        let hash = state.get_hash();
+        assert_eq!(
+            state.try_apply(&Action::BuildRoom(ROOM_INDICES[0])),
+            Err(RuleError::SpaceOccupied(ROOM_INDICES[0]))
+        );
+        assert_eq!(
+            state.try_apply(&Action::BuildRoom(NUM_FARMYARD_SPACES)),
+            Err(RuleError::InvalidFarmyardIndex(NUM_FARMYARD_SPACES))
+        );
+        state.current_player_quantities_mut()[Reed.index()] = 1;
+        let hash_without_reed = state.get_hash();
+        let mut room_cost = new_res();
+        room_cost[Wood.index()] = 5;
+        room_cost[Reed.index()] = 2;
+        assert_eq!(
+            state.try_apply(&Action::BuildRoom(0)),
+            Err(RuleError::NotEnoughResources(room_cost))
+        );
+        assert_eq!(state.get_hash(), hash_without_reed);
+        state.current_player_quantities_mut()[Reed.index()] = 2;
+        assert_eq!(state.get_hash(), hash);
+
+        // Moves out of range are refused, and can be printed
+        let occupation = Action::PlayOccupation(99, 0);
+        assert_eq!(
+            state.try_apply(&occupation),
+            Err(RuleError::IllegalAction(occupation.clone()))
+        );
+        assert_eq!(format!("{occupation:?}"), "Play Occupation (99)");
+        for idx in 0..state.ruleset().max_stables {
+            state.current_farm_mut().build_stable(idx).unwrap();
+        }
+        let idx = state.ruleset().max_stables;
+        assert_eq!(
+            state.try_apply(&Action::BuildStable(idx)),
+            Err(RuleError::NoStablesLeft)
+        );
+
+        to_place_worker(&mut state);
+        assert_eq!(
+            state.try_apply(&Action::UseSpace(farm_expansion)),
+            Err(RuleError::ActionSpaceOccupied(farm_expansion))
+        );
+    }
+
+    #[test]
+    fn test_game_ends_without_final_harvest() {
+        let ruleset = Ruleset {
+            harvest_rounds: vec![4, 7],
+            ..Ruleset::default()
+        };
+        let mut state = State::with_ruleset_and_seed(&[PlayerType::Human; 2], ruleset, 13).unwrap();
+        state.play_random(None);
+        assert!(matches!(state.last_action, Action::EndGame));
+        assert_eq!(state.current_round, 14);
+    }
 }
@@ -0,0 +1,316 @@
+use super::farm::{NEIGHBOR_SPACES, NUM_FARMYARD_SPACES};
+use super::fencing::{fences_required, get_existing_pastures};
+use super::flag::{ClayHouse, Flag, HasRoomToGrow, StoneHouse, WoodHouse};
+use super::quantity::{AdultMembers, MembersPlacedThisRound, Quantity, Rooms};
+use super::state::State;
+use std::fmt::{Display, Formatter};
+
+/// A broken invariant of a `State`. A state reached by applying legal actions should never have any.
+#[derive(Clone, Debug, Hash, PartialEq)]
+pub enum Violation {
+    PeoplePlaced {
+        total: usize,
+        people_placed_this_round: usize,
+    },
+    TooManyPlaced {
+        player_idx: usize,
+        placed: usize,
+        adults: usize,
+    },
+    Rooms {
+        player_idx: usize,
+        quantity: usize,
+        on_farm: usize,
+    },
+    RoomToGrow {
+        player_idx: usize,
+        flag: bool,
+    },
+    HouseMaterial {
+        player_idx: usize,
+        materials: usize,
+    },
+    FamilySize {
+        player_idx: usize,
+        members: usize,
+    },
+    Stables {
+        player_idx: usize,
+        stables: usize,
+    },
+    DisconnectedPasture {
+        player_idx: usize,
+        pasture: Vec<usize>,
+    },
+    Fences {
+        player_idx: usize,
+        fences_used: usize,
+        layout: usize,
+    },
+    TooManyFences {
+        player_idx: usize,
+        fences_used: usize,
+    },
+    OccupiedSpace {
+        action_space_idx: usize,
+        player_idx: usize,
+    },
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PeoplePlaced {
+                total,
+                people_placed_this_round,
+            } => write!(
+                f,
+                "Players placed {total} people but {people_placed_this_round} are counted for the round"
+            ),
+            Self::TooManyPlaced {
+                player_idx,
+                placed,
+                adults,
+            } => write!(
+                f,
+                "Player {player_idx} placed {placed} people with only {adults} adults"
+            ),
+            Self::Rooms {
+                player_idx,
+                quantity,
+                on_farm,
+            } => write!(
+                f,
+                "Player {player_idx} has {quantity} rooms but {on_farm} on the farm"
+            ),
+            Self::RoomToGrow { player_idx, flag } => write!(
+                f,
+                "Player {player_idx} has room to grow set to {flag} which does not match rooms and family"
+            ),
+            Self::HouseMaterial {
+                player_idx,
+                materials,
+            } => write!(
+                f,
+                "Player {player_idx} has {materials} house materials instead of one"
+            ),
+            Self::FamilySize {
+                player_idx,
+                members,
+            } => write!(
+                f,
+                "Player {player_idx} has {members} family members, more than allowed"
+            ),
+            Self::Stables {
+                player_idx,
+                stables,
+            } => write!(
+                f,
+                "Player {player_idx} has {stables} stables, more than allowed"
+            ),
+            Self::DisconnectedPasture {
+                player_idx,
+                pasture,
+            } => write!(
+                f,
+                "Player {player_idx} has a pasture on disconnected spaces {pasture:?}"
+            ),
+            Self::Fences {
+                player_idx,
+                fences_used,
+                layout,
+            } => write!(
+                f,
+                "Player {player_idx} used {fences_used} fences but the pastures need {layout}"
+            ),
+            Self::TooManyFences {
+                player_idx,
+                fences_used,
+            } => write!(
+                f,
+                "Player {player_idx} used {fences_used} fences, more than allowed"
+            ),
+            Self::OccupiedSpace {
+                action_space_idx,
+                player_idx,
+            } => write!(
+                f,
+                "Action space {action_space_idx} is occupied by player {player_idx} but is not available"
+            ),
+        }
+    }
+}
+
+// Whether all spaces of a pasture are reachable from its first space without leaving it
+fn is_connected(pasture: &[usize]) -> bool {
+    let mut reached = [false; NUM_FARMYARD_SPACES];
+    let mut stack = vec![pasture[0]];
+    reached[pasture[0]] = true;
+    while let Some(idx) = stack.pop() {
+        for &n in NEIGHBOR_SPACES[idx].iter().flatten() {
+            if !reached[n] && pasture.contains(&n) {
+                reached[n] = true;
+                stack.push(n);
+            }
+        }
+    }
+    pasture.iter().all(|&idx| reached[idx])
+}
+
+fn validate_player(state: &State, player_idx: usize, ret: &mut Vec<Violation>) {
+    let quantities = state.player_quantities(player_idx);
+    let flags = state.player_flags(player_idx);
+    let farm = state.player_farm(player_idx);
+    let ruleset = state.ruleset();
+
+    if quantities[MembersPlacedThisRound.index()] > quantities[AdultMembers.index()] {
+        ret.push(Violation::TooManyPlaced {
+            player_idx,
+            placed: quantities[MembersPlacedThisRound.index()],
+            adults: quantities[AdultMembers.index()],
+        });
+    }
+
+    let on_farm = farm.room_indices().len();
+    if quantities[Rooms.index()] != on_farm {
+        ret.push(Violation::Rooms {
+            player_idx,
+            quantity: quantities[Rooms.index()],
+            on_farm,
+        });
+    }
+
+    let members = state.family_members(player_idx);
+    let room_to_grow = quantities[Rooms.index()] > members;
+    if flags[HasRoomToGrow.index()] != room_to_grow {
+        ret.push(Violation::RoomToGrow {
+            player_idx,
+            flag: flags[HasRoomToGrow.index()],
+        });
+    }
+
+    let materials = [WoodHouse.index(), ClayHouse.index(), StoneHouse.index()]
+        .iter()
+        .filter(|&&idx| flags[idx])
+        .count();
+    if materials != 1 {
+        ret.push(Violation::HouseMaterial {
+            player_idx,
+            materials,
+        });
+    }
+
+    if members > ruleset.max_family_members {
+        ret.push(Violation::FamilySize {
+            player_idx,
+            members,
+        });
+    }
+
+    let stables = farm.num_stables();
+    if stables > ruleset.max_stables {
+        ret.push(Violation::Stables {
+            player_idx,
+            stables,
+        });
+    }
+
+    for pasture in get_existing_pastures(&farm.farmyard_spaces) {
+        if !is_connected(&pasture) {
+            ret.push(Violation::DisconnectedPasture {
+                player_idx,
+                pasture,
+            });
+        }
+    }
+
+    let layout = fences_required(&farm.farmyard_spaces);
+    if farm.fences_used != layout {
+        ret.push(Violation::Fences {
+            player_idx,
+            fences_used: farm.fences_used,
+            layout,
+        });
+    }
+    if farm.fences_used > ruleset.max_fences {
+        ret.push(Violation::TooManyFences {
+            player_idx,
+            fences_used: farm.fences_used,
+        });
+    }
+}
+
+/// Checks every invariant of the state and returns all that are broken
+#[must_use]
+pub fn validate(state: &State) -> Vec<Violation> {
+    let mut ret = Vec::new();
+
+    let total = (0..state.num_players)
+        .map(|i| state.player_quantities(i)[MembersPlacedThisRound.index()])
+        .sum();
+    if total != state.people_placed_this_round {
+        ret.push(Violation::PeoplePlaced {
+            total,
+            people_placed_this_round: state.people_placed_this_round,
+        });
+    }
+
+    for player_idx in 0..state.num_players {
+        validate_player(state, player_idx, &mut ret);
+    }
+
+    let available = state.revealed_action_spaces();
+    for (action_space_idx, occupant) in state.occupied.iter().enumerate() {
+        if let Some(player_idx) = occupant {
+            if !available.contains(&action_space_idx) || *player_idx >= state.num_players {
+                ret.push(Violation::OccupiedSpace {
+                    action_space_idx,
+                    player_idx: *player_idx,
+                });
+            }
+        }
+    }
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agricola::algorithms::PlayerType;
+    use crate::agricola::farm::FarmyardSpace;
+
+    #[test]
+    fn test_detects_broken_invariants() {
+        let mut state = State::with_seed(&[PlayerType::MctsAI; 2], 7).unwrap();
+        assert!(validate(&state).is_empty());
+
+        state.people_placed_this_round = 1;
+        let farm = state.current_farm_mut();
+        farm.farmyard_spaces[0] = FarmyardSpace::FencedPasture(false, 0);
+        farm.farmyard_spaces[2] = FarmyardSpace::FencedPasture(false, 0);
+        farm.fences_used = 8;
+
+        let violations = validate(&state);
+        assert!(violations.contains(&Violation::PeoplePlaced {
+            total: 0,
+            people_placed_this_round: 1,
+        }));
+        assert!(violations
+            .iter()
+            .any(|v| matches!(v, Violation::DisconnectedPasture { .. })));
+        assert!(!violations
+            .iter()
+            .any(|v| matches!(v, Violation::Fences { .. })));
+    }
+
+    #[test]
+    fn test_random_games_stay_valid() {
+        for seed in 0..2 {
+            let mut state = State::with_seed(&[PlayerType::MctsAI; 3], seed).unwrap();
+            state.debug_validate = true;
+            state.play_random(None);
+            assert!(validate(&state).is_empty());
+        }
+    }
+}
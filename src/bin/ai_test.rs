@@ -2,9 +2,9 @@ use indicatif::ProgressBar;
 use std::{env, time::Instant};
 
 use agricola_game::agricola::{
-    actions::Action,
     algorithms::{PlayerType, AI},
-    state::State,
+    external::ExternalEngine,
+    game::Game,
 };
 
 fn main() {
@@ -12,41 +12,62 @@ fn main() {
     const OPT_DEPTH: Option<usize> = None;
     env::set_var("RUN_BACKTRACE", "1");
     let start = Instant::now();
-    let opt_state = State::new(&[PlayerType::MctsAI, PlayerType::MctsAI]);
-    let mut ai_agent = AI::new();
-    let mut state = opt_state.unwrap();
+    // Pass a seed to replay a game
+    let seed: u64 = env::args()
+        .nth(1)
+        .map_or_else(rand::random, |s| s.parse().expect("Seed must be a number"));
+    println!("Seed {seed}");
+    // Pass an external engine program and its arguments to play it as the second player
+    let engine_args: Vec<String> = env::args().skip(3).collect();
+    let mut engine = env::args().nth(2).map(|program| {
+        ExternalEngine::spawn(&program, &engine_args).expect("Could not start the engine")
+    });
+    let second_player = if engine.is_some() {
+        PlayerType::External
+    } else {
+        PlayerType::MctsAI
+    };
+    let mut game = Game::with_seed(&[PlayerType::MctsAI, second_player], seed).unwrap();
+    let mut ai_agent = AI::with_seed(seed);
 
-    loop {
-        let actions = Action::next_choices(&state);
-        if actions.is_empty() {
-            println!("GAME OVER");
-            break;
-        }
-
-        if actions.len() == 1 {
-            actions[0].0.apply_choice(&mut state);
-            println!("Auto-choosing single action [{:?}]", actions[0]);
+    while !game.is_over() {
+        let state = game.state();
+        if let Some(engine) = engine
+            .as_mut()
+            .filter(|_| state.player_type(state.current_player_idx) == PlayerType::External)
+        {
+            let player_idx = state.current_player_idx;
+            let action = engine
+                .choose_move(state, NUM_GAMES_TO_SIMULATE)
+                .expect("The engine failed");
+            let label = action.describe(state);
+            game.play(&action).unwrap();
+            println!("Player {player_idx} (external) chose action [{label}]");
             continue;
         }
 
-        let mut records = AI::get_simulation_records(&state);
+        let mut records = AI::get_simulation_records(state);
         let bar = ProgressBar::new(NUM_GAMES_TO_SIMULATE as u64);
         for _ in 0..NUM_GAMES_TO_SIMULATE {
             bar.inc(1);
-            ai_agent.sample_once(&mut records, &state, OPT_DEPTH);
+            ai_agent.sample_once(&mut records, state, OPT_DEPTH);
         }
         bar.finish();
 
         println!("Scores {:?}", state.scores());
-        records[0].action.apply_choice(&mut state);
-        println!(
-            "Player {} chose action [{:?}]",
-            state.current_player_idx, records[0].action
-        );
+        let player_idx = state.current_player_idx;
+        let action = records[0].action.clone();
+        let label = action.describe(state);
+        game.play(&action).unwrap();
+        println!("Player {player_idx} chose action [{label}]");
     }
+    println!("GAME OVER");
     let duration = start.elapsed();
     println!(
         "Time taken in a {} player MCTS AI game (Simulated Games {}, Depth {:?}): {:?}",
-        state.num_players, NUM_GAMES_TO_SIMULATE, OPT_DEPTH, duration
+        game.state().num_players,
+        NUM_GAMES_TO_SIMULATE,
+        OPT_DEPTH,
+        duration
     );
 }
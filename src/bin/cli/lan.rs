@@ -0,0 +1,134 @@
+// Playing at one table from several terminals. The hosting `cli` holds the game and sends every
+// joined `cli` what its seat sees, the joined ones only send back the moves of their seat.
+// Messages are JSON, one per line.
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PlayerView {
+    pub title: String,
+    pub farm: String,
+    pub resources: String,
+}
+
+/// The table as drawn for one seat. The engine has no hidden cards, so everything on the table is public,
+/// only the seed is kept on the host since it decides the round cards to come.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TableView {
+    pub board: String,
+    pub players: Vec<PlayerView>,
+    // In notation and as shown, only when the seat is to play
+    pub moves: Vec<(String, String)>,
+    pub status: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum ClientMessage {
+    Play(String),
+}
+
+/// Sends and receives messages on a TCP stream. Lines are read on their own thread, so receiving never blocks.
+pub struct Connection {
+    writer: TcpStream,
+    incoming: Receiver<String>,
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        let reader = BufReader::new(stream.try_clone()?);
+        let (sender, incoming) = mpsc::channel();
+        thread::spawn(move || {
+            for line in reader.lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self {
+            writer: stream,
+            incoming,
+        })
+    }
+
+    pub fn connect<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        Self::new(TcpStream::connect(address)?)
+    }
+
+    pub fn send<T: Serialize>(&mut self, message: &T) -> io::Result<()> {
+        let line = serde_json::to_string(message)?;
+        writeln!(self.writer, "{line}")
+    }
+
+    /// Messages received since the last call, lines that are not a message are skipped
+    /// # Errors
+    /// Once the other side has closed the connection
+    pub fn receive<T: DeserializeOwned>(&mut self) -> io::Result<Vec<T>> {
+        let mut messages = Vec::new();
+        loop {
+            match self.incoming.try_recv() {
+                Ok(line) => messages.extend(serde_json::from_str(&line).ok()),
+                Err(TryRecvError::Empty) => return Ok(messages),
+                Err(TryRecvError::Disconnected) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::ConnectionAborted,
+                        "The connection is closed",
+                    ))
+                }
+            }
+        }
+    }
+}
+
+/// A player connected to the host, with the seat they control if any is free
+pub struct RemotePlayer {
+    pub seat: Option<usize>,
+    pub connection: Connection,
+}
+
+pub struct Host {
+    listener: TcpListener,
+    pub players: Vec<RemotePlayer>,
+}
+
+impl Host {
+    pub fn bind<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            players: Vec::new(),
+        })
+    }
+
+    /// Accepts the players waiting to join, returns whether any joined
+    pub fn accept(&mut self) -> bool {
+        let mut joined = false;
+        while let Ok((stream, _)) = self.listener.accept() {
+            let connection = stream
+                .set_nonblocking(false)
+                .and_then(|()| Connection::new(stream));
+            if let Ok(connection) = connection {
+                self.players.push(RemotePlayer {
+                    seat: None,
+                    connection,
+                });
+                joined = true;
+            }
+        }
+        joined
+    }
+
+    /// Gives the seats to the players in the order they joined, the others watch
+    pub fn assign_seats(&mut self, seats: &[usize]) {
+        for (i, player) in self.players.iter_mut().enumerate() {
+            player.seat = seats.get(i).copied();
+        }
+    }
+}
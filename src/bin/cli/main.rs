@@ -0,0 +1,896 @@
+mod lan;
+
+use std::env;
+use std::time::{Duration, Instant};
+use std::{error::Error, io};
+
+use agricola_game::agricola::algorithms::SimulationRecord;
+use agricola_game::agricola::display::{print_farm, print_resources};
+use agricola_game::agricola::external::ExternalEngine;
+use agricola_game::agricola::quantity::{Quantity, Wood};
+use agricola_game::agricola::state::MAX_NUM_PLAYERS;
+use agricola_game::agricola::{
+    actions::Action,
+    algorithms::{PlayerType, AI},
+    game::Game,
+};
+use crossterm::{
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use lan::{ClientMessage, Connection, Host, PlayerView, TableView};
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use ratatui::style::{Color, Style};
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    layout::{Constraint, Direction, Layout, Rect},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+    Frame, Terminal,
+};
+
+const NUM_GAMES_TO_SIMULATE_PER_MOVE: usize = 50;
+const DEPTH: Option<usize> = None;
+const SAVE_FILE: &str = "agricola_save.json";
+
+const PLAYER_COLORS: [Color; 4] = [Color::Red, Color::Green, Color::Blue, Color::Yellow];
+
+#[derive(Clone, Copy, Debug)]
+enum PlayerSelection {
+    Empty,
+    Human,
+    MctsAI,
+    TdAI,
+    External,
+}
+
+const PLAYER_TYPES: [PlayerSelection; 5] = [
+    PlayerSelection::Empty,
+    PlayerSelection::Human,
+    PlayerSelection::MctsAI,
+    PlayerSelection::TdAI,
+    PlayerSelection::External,
+];
+
+struct App {
+    selection_x: usize,
+    selection_y: usize,
+    player_selections: [PlayerSelection; 4],
+    game: Option<Game>,
+    menu_active: bool,
+    num_selections_y: usize,
+    move_selected: bool,
+    ai: [Option<AI>; MAX_NUM_PLAYERS],
+    // Program starting the engine of the External players and its arguments, given with --engine
+    engine_command: Vec<String>,
+    engines: [Option<ExternalEngine>; MAX_NUM_PLAYERS],
+    // Version of the game the engine to move is searching, None when no search is running
+    engine_searching: Option<u64>,
+    records: Vec<SimulationRecord>,
+    current_actions: Vec<Action>,
+    player_colors: [Color; 4],
+    message: String,
+    // Set when hosting a game for players joining from other terminals
+    host: Option<Host>,
+    // Set when playing at a table hosted by another `cli`, which sends the view of our seat
+    joined: Option<Connection>,
+    view: Option<TableView>,
+    // Bumped whenever the game changes, so joined players are only sent new views
+    version: u64,
+    version_sent: u64,
+    // Draws the colors, the seeds of new games and of the AIs, so a session can be replayed with --seed
+    rng: ChaCha8Rng,
+}
+
+impl App {
+    fn new(seed: u64) -> App {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut player_colors = PLAYER_COLORS;
+        player_colors.shuffle(&mut rng);
+        App {
+            selection_x: 0,
+            selection_y: 0,
+            player_selections: [PlayerSelection::Empty; 4],
+            game: None,
+            menu_active: false,
+            num_selections_y: 1,
+            move_selected: false,
+            ai: [const { None }; MAX_NUM_PLAYERS],
+            engine_command: Vec::new(),
+            engines: [const { None }; MAX_NUM_PLAYERS],
+            engine_searching: None,
+            records: Vec::new(),
+            current_actions: Vec::new(),
+            player_colors,
+            message: String::new(),
+            host: None,
+            joined: None,
+            view: None,
+            version: 0,
+            version_sent: 0,
+            rng,
+        }
+    }
+
+    fn next_x(&mut self) {
+        if self.menu_active {
+            self.selection_x = (self.selection_x + 1) % 4;
+        }
+    }
+
+    fn previous_x(&mut self) {
+        if self.menu_active {
+            if self.selection_x == 0 {
+                self.selection_x = 3;
+            } else {
+                self.selection_x -= 1;
+            }
+        }
+    }
+
+    fn next_y(&mut self) {
+        self.selection_y = (self.selection_y + 1) % self.num_selections_y;
+        if self.menu_active {
+            self.player_selections[self.selection_x] = PLAYER_TYPES[self.selection_y];
+        }
+    }
+
+    fn previous_y(&mut self) {
+        if self.selection_y == 0 {
+            self.selection_y = self.num_selections_y - 1;
+        } else {
+            self.selection_y -= 1;
+        }
+
+        if self.menu_active {
+            self.player_selections[self.selection_x] = PLAYER_TYPES[self.selection_y];
+        }
+    }
+
+    fn show_menu(&self) -> String {
+        let mut ret: String = "Choose the Player Types : ".to_string();
+        for (i, player_selection) in self.player_selections.iter().enumerate() {
+            if i == self.selection_x {
+                ret.push_str(&format!("[{player_selection:?}] "));
+            } else {
+                ret.push_str(&format!("{player_selection:?} "));
+            }
+        }
+
+        ret.push_str("\nPress 'Enter' to Start a New Game ");
+        ret
+    }
+
+    fn on_tick(&mut self) {
+        if self.joined.is_some() {
+            self.tick_joined();
+            return;
+        }
+        self.tick_host();
+        if self.menu_active {
+            return;
+        }
+        if self.current_actions.is_empty() {
+            return;
+        }
+
+        // Moves of the other terminals' seats are played when they arrive
+        let remote_to_play = self
+            .game
+            .as_ref()
+            .and_then(Game::current_player)
+            .is_some_and(|idx| self.is_remote_seat(idx));
+        if let Some(game) = &mut self.game {
+            let state = game.state();
+            let action = match state.player_type(state.current_player_idx) {
+                PlayerType::Human => {
+                    if remote_to_play || !self.move_selected {
+                        return;
+                    }
+                    self.current_actions[self.selection_y].clone()
+                }
+                PlayerType::MctsAI | PlayerType::TdAI => {
+                    let ai = self.ai[state.current_player_idx].as_mut().unwrap();
+                    if ai.num_games_sampled == 0 {
+                        self.records = AI::get_simulation_records(state);
+                    }
+
+                    let total_games_to_simulate =
+                        NUM_GAMES_TO_SIMULATE_PER_MOVE * self.records.len();
+                    if !self.move_selected && ai.num_games_sampled < total_games_to_simulate {
+                        ai.sample_once(&mut self.records, state, DEPTH);
+                        AI::sort_records(&mut self.records);
+                        return;
+                    }
+
+                    ai.reset();
+                    self.records[0].action.clone()
+                }
+                PlayerType::External => {
+                    let player_idx = state.current_player_idx;
+                    let Some(engine) = self.engines[player_idx].as_mut() else {
+                        return;
+                    };
+                    // The engine searches on its own while the screen keeps being drawn, a search of a
+                    // position that was undone is replaced by one of the current position
+                    let mut reply = Ok(None);
+                    if self.engine_searching != Some(self.version) {
+                        let samples = NUM_GAMES_TO_SIMULATE_PER_MOVE * self.current_actions.len();
+                        reply = engine.start_move(state, samples).map(|()| None);
+                        self.engine_searching = Some(self.version);
+                    }
+                    if reply.is_ok() {
+                        reply = engine.poll_move(state);
+                    }
+                    match reply {
+                        Ok(Some(action)) => {
+                            self.engine_searching = None;
+                            action
+                        }
+                        Ok(None) => return,
+                        Err(e) => {
+                            // The engine is stopped, the game waits until it is undone or a new one starts
+                            self.message = format!("Player {player_idx} : {e}");
+                            self.engines[player_idx] = None;
+                            self.engine_searching = None;
+                            return;
+                        }
+                    }
+                }
+            };
+            // Moves come from the legal moves of the game, so they are always accepted
+            game.play(&action).unwrap();
+            self.after_move();
+        }
+    }
+
+    fn after_move(&mut self) {
+        if let Some(game) = &self.game {
+            self.move_selected = false;
+            self.current_actions = game.legal_moves();
+            self.num_selections_y = self.current_actions.len();
+            self.selection_y = 0;
+            self.version += 1;
+        }
+    }
+
+    // Human seats played from other terminals when hosting, every human seat but the first
+    fn remote_seats(&self) -> Vec<usize> {
+        let Some(game) = &self.game else {
+            return Vec::new();
+        };
+        let state = game.state();
+        (0..state.num_players)
+            .filter(|&idx| state.player_type(idx) == PlayerType::Human)
+            .skip(1)
+            .collect()
+    }
+
+    fn is_remote_seat(&self, player_idx: usize) -> bool {
+        self.host.is_some() && self.remote_seats().contains(&player_idx)
+    }
+
+    fn tick_host(&mut self) {
+        let Some(mut host) = self.host.take() else {
+            return;
+        };
+        let joined = host.accept();
+
+        // Moves with the index of the player who sent them among those still connected
+        let mut moves = Vec::new();
+        let mut kept = 0;
+        let mut left = false;
+        host.players.retain_mut(
+            |player| match player.connection.receive::<ClientMessage>() {
+                Ok(messages) => {
+                    moves.extend(
+                        messages
+                            .into_iter()
+                            .map(|ClientMessage::Play(notation)| (kept, player.seat, notation)),
+                    );
+                    kept += 1;
+                    true
+                }
+                Err(_) => {
+                    left = true;
+                    false
+                }
+            },
+        );
+        // A join and a leave in the same tick keep the count, the seats still move up
+        if joined || left {
+            host.assign_seats(&self.remote_seats());
+            self.version += 1;
+        }
+
+        for (player_idx, seat, notation) in moves {
+            if let Err(e) = self.play_remote(seat, &notation) {
+                // The player cleared their moves when sending, they get them back along with the reason
+                if let Some(mut view) = self.seat_view(seat) {
+                    view.status = if view.status.is_empty() {
+                        e
+                    } else {
+                        format!("{e}\n{}", view.status)
+                    };
+                    let _ = host.players[player_idx].connection.send(&view);
+                }
+            }
+        }
+
+        if self.version != self.version_sent {
+            for player in &mut host.players {
+                if let Some(view) = self.seat_view(player.seat) {
+                    // A player who left is dropped on the next tick
+                    let _ = player.connection.send(&view);
+                }
+            }
+            self.version_sent = self.version;
+        }
+        self.host = Some(host);
+    }
+
+    // Plays a move sent by a joined player, if it is their seat's turn
+    fn play_remote(&mut self, seat: Option<usize>, notation: &str) -> Result<(), String> {
+        let game = self.game.as_mut().ok_or("No game is being played")?;
+        if seat.is_none() {
+            return Err(String::from("Only seated players can play"));
+        }
+        if game.current_player() != seat {
+            return Err(String::from("It is not your turn"));
+        }
+        let action = Action::from_notation(notation, game.state())?;
+        game.play(&action).map_err(|e| e.to_string())?;
+        self.after_move();
+        Ok(())
+    }
+
+    fn tick_joined(&mut self) {
+        let Some(connection) = &mut self.joined else {
+            return;
+        };
+        match connection.receive::<TableView>() {
+            Ok(views) => {
+                if let Some(view) = views.into_iter().last() {
+                    self.num_selections_y = view.moves.len().max(1);
+                    self.selection_y = 0;
+                    self.view = Some(view);
+                }
+            }
+            Err(_) => self.message = String::from("Disconnected from the host"),
+        }
+
+        if self.move_selected {
+            self.move_selected = false;
+            if let Some(view) = &mut self.view {
+                if let Some((notation, _)) = view.moves.get(self.selection_y) {
+                    let _ = connection.send(&ClientMessage::Play(notation.clone()));
+                    // Sent once, the host replies with the next view
+                    view.moves.clear();
+                }
+            }
+        }
+    }
+
+    // The table as drawn, for the local game or the one hosted by another terminal
+    fn table_view(&self) -> Option<TableView> {
+        if self.joined.is_some() {
+            return self.view.clone();
+        }
+        let state = self.game.as_ref()?.state();
+        let scores = state.scores();
+        let players = (0..state.num_players)
+            .map(|i| {
+                let mut title = format!(
+                    " Player {} | {:?} | {} Points",
+                    i + 1,
+                    state.player_type(i),
+                    scores[i]
+                );
+                if i == state.starting_player_idx {
+                    title = format!("{title} | 🟡 ");
+                }
+                if i == state.current_player_idx {
+                    title = format!("{title} | 🔻 ");
+                }
+                PlayerView {
+                    title,
+                    farm: print_farm(state.player_farm(i), state.room_material_idx(i)),
+                    resources: print_resources(state, i),
+                }
+            })
+            .collect();
+        Some(TableView {
+            board: state.format(),
+            players,
+            moves: Vec::new(),
+            status: String::new(),
+        })
+    }
+
+    // What a joined player sees, with the moves of their seat when it is their turn
+    fn seat_view(&self, seat: Option<usize>) -> Option<TableView> {
+        let game = self.game.as_ref()?;
+        let mut view = self.table_view()?;
+        view.status = match game.current_player() {
+            None => String::from("GAME OVER!"),
+            Some(idx) if Some(idx) == seat => {
+                view.moves = self
+                    .current_actions
+                    .iter()
+                    .map(|action| (action.notation(), action.describe(game.state())))
+                    .collect();
+                String::new()
+            }
+            Some(idx) => format!(
+                "Waiting for Player {} ({:?})",
+                idx + 1,
+                game.state().player_type(idx)
+            ),
+        };
+        Some(view)
+    }
+
+    fn actions_text(&self, view: &TableView) -> String {
+        if self.joined.is_none() {
+            return self.format_next_actions();
+        }
+        let mut ret = view.status.clone();
+        for (i, (_, label)) in view.moves.iter().enumerate() {
+            if i == self.selection_y {
+                ret.push_str(&format!("\n>> {label}"));
+            } else {
+                ret.push_str(&format!("\n{label}"));
+            }
+        }
+        format!("{ret}\n\n\n{}", self.message)
+    }
+
+    fn start_new_game(&mut self) {
+        let mut players = Vec::new();
+        for (i, player_selection) in self.player_selections.iter().enumerate() {
+            match player_selection {
+                PlayerSelection::Human => players.push(PlayerType::Human),
+                PlayerSelection::MctsAI => {
+                    players.push(PlayerType::MctsAI);
+                    self.ai[i] = Some(AI::with_seed(self.rng.gen()));
+                }
+                PlayerSelection::TdAI => {
+                    players.push(PlayerType::TdAI);
+                    self.ai[i] = Some(AI::with_seed(self.rng.gen()));
+                }
+                PlayerSelection::External => {
+                    players.push(PlayerType::External);
+                    match self.spawn_engine() {
+                        Ok(engine) => self.engines[i] = Some(engine),
+                        Err(e) => {
+                            self.message = e;
+                            return;
+                        }
+                    }
+                }
+                PlayerSelection::Empty => (),
+            }
+        }
+        if let Some(game) = Game::with_seed(&players, self.rng.gen()) {
+            self.set_game(game);
+        }
+    }
+
+    fn spawn_engine(&self) -> Result<ExternalEngine, String> {
+        let (program, args) = self
+            .engine_command
+            .split_first()
+            .ok_or("Pass --engine <program> [args] to play against an external engine")?;
+        ExternalEngine::spawn(program, args)
+    }
+
+    // Resumes a saved game, with fresh AI agents for its AI players
+    fn load_game(&mut self, path: &str) -> Result<(), String> {
+        let game = Game::load(path)?;
+        for i in 0..game.state().num_players {
+            self.ai[i] = match game.state().player_type(i) {
+                PlayerType::Human | PlayerType::External => None,
+                PlayerType::MctsAI | PlayerType::TdAI => Some(AI::with_seed(self.rng.gen())),
+            };
+            if game.state().player_type(i) == PlayerType::External {
+                self.engines[i] = Some(self.spawn_engine()?);
+            }
+        }
+        self.set_game(game);
+        Ok(())
+    }
+
+    fn set_game(&mut self, game: Game) {
+        self.menu_active = false;
+        self.records.clear();
+        self.game = Some(game);
+        self.after_move();
+        let seats = self.remote_seats();
+        if let Some(host) = &mut self.host {
+            host.assign_seats(&seats);
+        }
+    }
+
+    fn save_game(&mut self) {
+        if let Some(game) = &self.game {
+            self.message = match game.save(SAVE_FILE, true) {
+                Ok(()) => format!("Saved to {SAVE_FILE}"),
+                Err(e) => e,
+            };
+        }
+    }
+
+    // Takes back moves until a human player is to move again, so AI replies are undone as well
+    fn undo(&mut self) {
+        if self.menu_active {
+            return;
+        }
+        if let Some(game) = &mut self.game {
+            while game.undo() {
+                let state = game.state();
+                if state.player_type(state.current_player_idx) == PlayerType::Human {
+                    break;
+                }
+            }
+
+            for ai in self.ai.iter_mut().flatten() {
+                ai.reset();
+            }
+            self.records.clear();
+            self.after_move();
+        }
+    }
+
+    pub fn format_next_actions(&self) -> String {
+        if self.current_actions.is_empty() {
+            return "GAME OVER!".to_string();
+        }
+
+        let mut ret: String = String::new();
+        let mut additional_stuff: String = String::new();
+        if let Some(game) = &self.game {
+            let state = game.state();
+            match state.player_type(state.current_player_idx) {
+                PlayerType::Human if self.is_remote_seat(state.current_player_idx) => {
+                    ret = format!(
+                        "Waiting for Player {} to play from another terminal..",
+                        state.current_player_idx + 1
+                    );
+                }
+                PlayerType::Human => {
+                    for (i, action) in self.current_actions.iter().enumerate() {
+                        if i == self.selection_y {
+                            ret.push_str(&format!("\n>> {}", action.describe(state)));
+                            if let Action::Fence(pc) = action {
+                                let mut farm = state.current_farm().clone();
+                                let mut wood = state.current_player_quantities()[Wood.index()];
+                                farm.fence_spaces(pc, &mut wood);
+                                let room_material_index =
+                                    state.room_material_idx(state.current_player_idx);
+                                additional_stuff = print_farm(&farm, room_material_index);
+                            }
+                        } else {
+                            ret.push_str(&format!("\n{}", action.describe(state)));
+                        }
+                    }
+                }
+                PlayerType::MctsAI | PlayerType::TdAI => {
+                    let ai = self.ai[state.current_player_idx].as_ref().unwrap();
+                    let total_games_to_simulate =
+                        NUM_GAMES_TO_SIMULATE_PER_MOVE * self.records.len();
+                    ret = format!(
+                        "{}/{} Games Simulated..\nCache Size {}\n",
+                        ai.num_games_sampled,
+                        total_games_to_simulate,
+                        ai.cache.len()
+                    );
+
+                    for (i, rec) in self.records.iter().enumerate() {
+                        if i == 0 {
+                            ret.push_str(&format!(
+                                "\n>> [{:.2} / {}] {}",
+                                rec.score,
+                                rec.games,
+                                rec.action.describe(state)
+                            ));
+
+                            if let Action::Fence(pc) = &rec.action {
+                                let mut farm = state.current_farm().clone();
+                                let mut wood = state.current_player_quantities()[Wood.index()];
+                                farm.fence_spaces(pc, &mut wood);
+                                let room_material_index =
+                                    state.room_material_idx(state.current_player_idx);
+                                additional_stuff = print_farm(&farm, room_material_index);
+                            }
+                        } else {
+                            ret.push_str(&format!(
+                                "\n[{:.2} / {}] {}",
+                                rec.score,
+                                rec.games,
+                                rec.action.describe(state)
+                            ));
+                        }
+                    }
+                }
+                PlayerType::External => {
+                    ret = format!(
+                        "Player {} is thinking in an external engine..",
+                        state.current_player_idx
+                    );
+                }
+            }
+
+            ret = format!("{ret}\n\n\n{additional_stuff}\n{}", self.message);
+        }
+        ret
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    env::set_var("RUN_BACKTRACE", "1");
+    // Pass the path of a saved game to resume it, and --engine <program> [args] to seat External players.
+    // --engine comes last, everything after it is the engine program and its arguments.
+    // --host <address> lets other terminals join with --join <address> and play the human seats after the first.
+    // --seed <number> replays the same games and AI searches, it goes first.
+    let mut args = env::args().skip(1).peekable();
+    let seed = if args.peek().is_some_and(|arg| arg == "--seed") {
+        args.nth(1)
+            .ok_or("--seed needs a number")?
+            .parse()
+            .map_err(|_| "Seed must be a number")?
+    } else {
+        rand::random()
+    };
+    let mut app = App::new(seed);
+    let mut save_path = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--engine" => {
+                app.engine_command = args.by_ref().collect();
+                if app.engine_command.is_empty() {
+                    return Err("--engine needs a program".into());
+                }
+            }
+            "--host" => {
+                app.host = Some(Host::bind(args.next().ok_or("--host needs an address")?)?);
+            }
+            "--join" => {
+                let address = args.next().ok_or("--join needs an address")?;
+                app.joined = Some(Connection::connect(address)?);
+            }
+            _ => save_path = Some(arg),
+        }
+    }
+    if let Some(path) = save_path {
+        app.load_game(&path)?;
+    }
+
+    // setup terminal
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    // create app and run it
+    let tick_rate = Duration::from_millis(1);
+    let res = run_app(&mut terminal, app, tick_rate);
+
+    // restore terminal
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
+    terminal.show_cursor()?;
+
+    if let Err(err) = res {
+        println!("{err:?}");
+    }
+
+    Ok(())
+}
+
+fn run_app<B: Backend>(
+    terminal: &mut Terminal<B>,
+    mut app: App,
+    tick_rate: Duration,
+) -> io::Result<()> {
+    let mut last_tick = Instant::now();
+    loop {
+        terminal.draw(|f| ui(f, &app))?;
+
+        let timeout = tick_rate
+            .checked_sub(last_tick.elapsed())
+            .unwrap_or_else(|| Duration::from_secs(0));
+        if crossterm::event::poll(timeout)? {
+            if let Event::Key(key) = event::read()? {
+                match key.code {
+                    KeyCode::Char('q') => {
+                        return Ok(());
+                    }
+                    KeyCode::Down => {
+                        app.next_y();
+                    }
+                    KeyCode::Up => {
+                        app.previous_y();
+                    }
+                    KeyCode::Right => {
+                        app.next_x();
+                    }
+                    KeyCode::Left => {
+                        app.previous_x();
+                    }
+                    KeyCode::Char('u') => {
+                        app.undo();
+                    }
+                    KeyCode::Char('s') => {
+                        app.save_game();
+                    }
+                    // A joined terminal plays the game of its host
+                    KeyCode::Char('n') if app.joined.is_none() => {
+                        app.menu_active = !app.menu_active;
+                        if app.menu_active {
+                            app.num_selections_y = PLAYER_TYPES.len();
+                            app.selection_y = 0;
+                        }
+                    }
+                    KeyCode::Enter => {
+                        if app.menu_active {
+                            app.start_new_game();
+                        } else {
+                            app.move_selected = true;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        if last_tick.elapsed() >= tick_rate {
+            app.on_tick();
+            last_tick = Instant::now();
+        }
+    }
+}
+
+#[allow(clippy::too_many_lines)]
+fn ui<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage(30),
+                Constraint::Percentage(40),
+                Constraint::Percentage(30),
+            ]
+            .as_ref(),
+        )
+        .split(f.size());
+
+    // Action Spaces
+    let block1 = Block::default()
+        .title("Board")
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded);
+
+    let block2 = Block::default()
+        .title("Farms")
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded);
+
+    let block3 = Block::default()
+        .title("Available Actions ('U' to Undo, 'S' to Save)")
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded);
+
+    if let Some(view) = app.table_view() {
+        // Board State
+        let text = Paragraph::new(view.board.clone())
+            .block(block1)
+            .style(Style::default());
+        f.render_widget(text, chunks[0]);
+
+        // Player farms
+        let n = u16::try_from(view.players.len()).unwrap();
+        let padding: u16 = 2;
+        let board_size = (100 - padding * (n - 1)) / n;
+        let mut constraints = vec![Constraint::Percentage(board_size)];
+        for _ in 1..n {
+            constraints.push(Constraint::Percentage(padding));
+            constraints.push(Constraint::Percentage(board_size));
+        }
+        let farm_areas = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(constraints)
+            .split(chunks[1]);
+        for (i, player) in view.players.iter().enumerate() {
+            let farm = Block::default()
+                .title(player.title.clone())
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(app.player_colors[i]));
+
+            f.render_widget(farm, farm_areas[2 * i]);
+
+            let displays = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(
+                    [
+                        Constraint::Percentage(45),
+                        Constraint::Percentage(10),
+                        Constraint::Percentage(45),
+                    ]
+                    .as_ref(),
+                )
+                .split(farm_areas[2 * i]);
+
+            let main_farm = Paragraph::new(player.farm.clone())
+                .style(Style::default())
+                .alignment(ratatui::layout::Alignment::Center);
+            f.render_widget(main_farm, displays[0]);
+
+            let resource_text = Paragraph::new(player.resources.clone());
+            f.render_widget(resource_text, displays[2]);
+        }
+
+        // Actions
+        let text = Paragraph::new(app.actions_text(&view))
+            .block(block3)
+            .style(Style::default());
+        f.render_widget(text, chunks[2]);
+    } else {
+        let waiting = if app.joined.is_some() {
+            "Waiting for the host to start a game"
+        } else {
+            "Press 'N' to set up a new game"
+        };
+        let text = Paragraph::new(waiting)
+            .block(block1)
+            .style(Style::default());
+        f.render_widget(text, chunks[0]);
+        f.render_widget(block2, chunks[1]);
+        f.render_widget(block3, chunks[2]);
+    }
+
+    if app.menu_active {
+        let block = Block::default()
+            .title("Create New Game")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Green));
+        let area = centered_rect(80, 40, f.size());
+        f.render_widget(Clear, area); //this clears out the background
+
+        let text = Paragraph::new(app.show_menu().to_string()).block(block);
+        f.render_widget(text, area);
+    }
+}
+
+/// helper function to create a centered rect using up certain percentage of the available rect `r`
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_y) / 2),
+                Constraint::Percentage(percent_y),
+                Constraint::Percentage((100 - percent_y) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_x) / 2),
+                Constraint::Percentage(percent_x),
+                Constraint::Percentage((100 - percent_x) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(popup_layout[1])[1]
+}
@@ -0,0 +1,246 @@
+// A line based protocol, in the spirit of UCI for chess engines, to drive the engine from another program.
+// One command per line on stdin, replies on stdout. Moves are written in the compact notation (`U3`, `FN13.14/8.9`, ...).
+//
+// agricola                      -> id name agricola_game / agricolaok
+// isready                       -> readyok
+// newgame <players> [seed]      -> ok, a new game with 1 to 4 players
+// position <position string>    -> ok, sets up a position written by `State::to_position_str`
+// moves <move> <move> ...       -> ok, plays the moves in order
+// play <move>                   -> ok
+// undo                          -> ok
+// legal                         -> legal <move> <move> ... (empty once the game is over)
+// show                          -> position <position string> / player <idx> / moves <record>
+// go [samples <n>] [millis <n>] [depth <n>]
+//                               -> info move <move> visits <n> score <s>, best first, then bestmove <move>
+// quit
+//
+// A command that fails replies error <reason> and leaves the game as it was.
+
+use std::io::{self, BufRead, Write};
+use std::time::{Duration, Instant};
+
+use agricola_game::agricola::{
+    actions::Action,
+    algorithms::{PlayerType, AI},
+    game::Game,
+    state::{State, MAX_NUM_PLAYERS},
+};
+
+const DEFAULT_SAMPLES: usize = 100;
+
+#[derive(Default)]
+struct SearchLimits {
+    samples: Option<usize>,
+    millis: Option<u64>,
+    depth: Option<usize>,
+}
+
+impl SearchLimits {
+    fn parse(args: &[&str]) -> Result<Self, String> {
+        let mut limits = Self::default();
+        for pair in args.chunks(2) {
+            let [key, value] = pair else {
+                return Err(format!("'{}' needs a value", pair[0]));
+            };
+            let value: u64 = value
+                .parse()
+                .map_err(|_| format!("'{value}' is not a number"))?;
+            match *key {
+                "samples" => limits.samples = Some(value as usize),
+                "millis" => limits.millis = Some(value),
+                "depth" => limits.depth = Some(value as usize),
+                _ => return Err(format!("Unknown search limit '{key}'")),
+            }
+        }
+        if limits.samples.is_none() && limits.millis.is_none() {
+            limits.samples = Some(DEFAULT_SAMPLES);
+        }
+        Ok(limits)
+    }
+
+    fn reached(&self, samples: usize, start: Instant) -> bool {
+        self.samples.is_some_and(|n| samples >= n)
+            || self
+                .millis
+                .is_some_and(|ms| start.elapsed() >= Duration::from_millis(ms))
+    }
+}
+
+struct Engine {
+    game: Option<Game>,
+    ai: AI,
+}
+
+impl Engine {
+    fn game(&self) -> Result<&Game, String> {
+        self.game
+            .as_ref()
+            .ok_or_else(|| String::from("No game, use newgame or position first"))
+    }
+
+    fn game_mut(&mut self) -> Result<&mut Game, String> {
+        self.game
+            .as_mut()
+            .ok_or_else(|| String::from("No game, use newgame or position first"))
+    }
+
+    fn set_game(&mut self, game: Game) {
+        self.game = Some(game);
+        self.ai.reset();
+    }
+
+    fn new_game(&mut self, args: &[&str]) -> Result<Vec<String>, String> {
+        let num_players: usize = args
+            .first()
+            .ok_or("newgame needs the number of players")?
+            .parse()
+            .map_err(|_| String::from("The number of players must be a number"))?;
+        if num_players == 0 || num_players > MAX_NUM_PLAYERS {
+            return Err(format!("Games have 1 to {MAX_NUM_PLAYERS} players"));
+        }
+        let seed = args.get(1).map_or_else(
+            || Ok(rand::random()),
+            |s| s.parse().map_err(|_| String::from("Seed must be a number")),
+        )?;
+        let players = vec![PlayerType::MctsAI; num_players];
+        self.set_game(Game::with_seed(&players, seed).ok_or("Could not start the game")?);
+        Ok(vec![String::from("ok")])
+    }
+
+    fn play_moves(&mut self, moves: &[&str]) -> Result<Vec<String>, String> {
+        // Play on a copy so a bad move leaves the game untouched
+        let mut game = self.game()?.clone();
+        game.play_record(&moves.join(" "))?;
+        self.game = Some(game);
+        Ok(vec![String::from("ok")])
+    }
+
+    fn show(&self) -> Result<Vec<String>, String> {
+        let game = self.game()?;
+        let mut lines = vec![format!("position {}", game.state().to_position_str())];
+        if let Some(player_idx) = game.current_player() {
+            lines.push(format!("player {player_idx}"));
+        }
+        lines.push(format!("moves {}", game.record()).trim_end().to_string());
+        Ok(lines)
+    }
+
+    fn go(&mut self, args: &[&str]) -> Result<Vec<String>, String> {
+        let limits = SearchLimits::parse(args)?;
+        let game = self
+            .game
+            .as_ref()
+            .ok_or("No game, use newgame or position first")?;
+        if game.is_over() {
+            return Err(String::from("The game is over"));
+        }
+
+        // The engine searches for whoever is to move, humans and other engines included
+        let mut state: State = game.state().clone();
+        let player_idx = state.current_player_idx;
+        if matches!(
+            state.player_type(player_idx),
+            PlayerType::Human | PlayerType::External
+        ) {
+            state.set_player_type(player_idx, PlayerType::MctsAI);
+        }
+
+        let mut records = AI::get_simulation_records(&state);
+        if records.is_empty() {
+            // Only moves the AI never considers are left, pick the first legal one
+            let action = game.legal_moves()[0].notation();
+            return Ok(vec![format!("bestmove {action}")]);
+        }
+
+        let start = Instant::now();
+        let mut samples = 0;
+        while !limits.reached(samples, start) {
+            self.ai.sample_once(&mut records, &state, limits.depth);
+            samples += 1;
+        }
+        AI::sort_records(&mut records);
+
+        let mut lines: Vec<String> = records
+            .iter()
+            .map(|record| {
+                format!(
+                    "info move {} visits {} score {:.3}",
+                    record.action.notation(),
+                    record.games,
+                    record.score
+                )
+            })
+            .collect();
+        lines.push(format!("bestmove {}", records[0].action.notation()));
+        Ok(lines)
+    }
+
+    fn handle(&mut self, command: &str, args: &[&str]) -> Result<Vec<String>, String> {
+        match command {
+            "agricola" => Ok(vec![
+                String::from("id name agricola_game"),
+                String::from("agricolaok"),
+            ]),
+            "isready" => Ok(vec![String::from("readyok")]),
+            "newgame" => self.new_game(args),
+            "position" => {
+                let state = State::from_position_str(&args.join(" "))?;
+                self.set_game(Game::from_state(state));
+                Ok(vec![String::from("ok")])
+            }
+            "moves" | "play" => self.play_moves(args),
+            "undo" => {
+                if self.game_mut()?.undo() {
+                    Ok(vec![String::from("ok")])
+                } else {
+                    Err(String::from("Nothing to undo"))
+                }
+            }
+            "legal" => {
+                let moves: Vec<String> = self
+                    .game()?
+                    .legal_moves()
+                    .iter()
+                    .map(Action::notation)
+                    .collect();
+                Ok(vec![format!("legal {}", moves.join(" "))
+                    .trim_end()
+                    .to_string()])
+            }
+            "show" => self.show(),
+            "go" => self.go(args),
+            _ => Err(format!("Unknown command '{command}'")),
+        }
+    }
+}
+
+fn main() {
+    let mut engine = Engine {
+        game: None,
+        ai: AI::new(),
+    };
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+
+    for line in stdin.lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((command, args)) = words.split_first() else {
+            continue;
+        };
+        if *command == "quit" {
+            break;
+        }
+        match engine.handle(command, args) {
+            Ok(lines) => {
+                for reply in lines {
+                    writeln!(stdout, "{reply}").unwrap();
+                }
+            }
+            Err(e) => writeln!(stdout, "error {e}").unwrap(),
+        }
+        stdout.flush().unwrap();
+    }
+}
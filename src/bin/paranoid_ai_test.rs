@@ -1,21 +1,15 @@
 use std::env;
 
-use agricola_game::agricola::{algorithms::PlayerType, paranoid_ai::best_move, state::State};
+use agricola_game::agricola::{algorithms::PlayerType, game::Game, paranoid_ai::best_move};
 
 fn main() {
     env::set_var("RUN_BACKTRACE", "1");
-    let opt_state = State::new(&[PlayerType::MctsAI, PlayerType::MctsAI]);
-    let mut state = opt_state.unwrap();
+    let mut game = Game::new(&[PlayerType::MctsAI, PlayerType::MctsAI]).unwrap();
 
-    loop {
-        let opt_action = best_move(&state);
-        if let Some(action) = opt_action {
-            action.apply_choice(&mut state);
-        } else {
-            println!("GAME OVER");
-            break;
-        }
+    while let Some(action) = best_move(game.state()) {
+        game.play(&action).unwrap();
     }
+    println!("GAME OVER");
 
-    println!("Scores {:?}", state.scores());
+    println!("Scores {:?}", game.state().scores());
 }
@@ -3,9 +3,11 @@ use std::env;
 use agricola_game::agricola::{
     actions::Action,
     algorithms::PlayerType,
+    game::Game,
     state::{State, MAX_NUM_PLAYERS},
 };
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 
 #[derive(Debug, Eq, PartialEq, Hash)]
 enum Pattern {
@@ -80,30 +82,25 @@ fn add_to_stats(statistics: &mut Vec<Statistics>, state: &State, action: &Action
     }
 }
 
-fn sim_one_game(players: &[PlayerType]) -> Vec<Statistics> {
-    let opt_state = State::new(players);
-    let mut state = opt_state.unwrap();
+fn sim_one_game(players: &[PlayerType], rng: &mut ChaCha8Rng) -> Vec<Statistics> {
+    let mut game = Game::with_seed(players, rng.gen()).unwrap();
+    // Forced moves count towards the patterns too
+    game.set_auto_apply_forced(false);
     let mut statistics = empty_stats(players.len());
 
     loop {
-        let choices = Action::next_choices(&state);
-        if choices.is_empty() {
+        let moves = game.legal_moves();
+        if moves.is_empty() {
             break;
         }
 
-        let action_idx = if choices.len() == 1 {
-            // Only one choice, play it
-            0
-        } else {
-            // Chose a random action
-            rand::thread_rng().gen_range(0..choices.len())
-        };
-
-        choices[action_idx].0.apply_choice(&mut state);
-        add_to_stats(&mut statistics, &state, &choices[action_idx].0);
+        // Chose a random action
+        let action = &moves[rng.gen_range(0..moves.len())];
+        game.play(action).unwrap();
+        add_to_stats(&mut statistics, game.state(), action);
     }
 
-    let fitness = state.fitness();
+    let fitness = game.state().fitness();
 
     for stat in &mut statistics {
         stat.average_fitness.clone_from(&fitness);
@@ -115,13 +112,19 @@ fn sim_one_game(players: &[PlayerType]) -> Vec<Statistics> {
 fn main() {
     const NUM_SIMS: usize = 100;
     env::set_var("RUN_BACKTRACE", "1");
+    // Pass a seed to replay the simulations
+    let seed: u64 = env::args()
+        .nth(1)
+        .map_or_else(rand::random, |s| s.parse().expect("Seed must be a number"));
+    println!("Seed {seed}");
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
     let players = vec![PlayerType::MctsAI, PlayerType::MctsAI];
 
     let mut statistics = empty_stats(players.len());
 
     for i in 0..NUM_SIMS {
         println!("#{i} {statistics:?}");
-        let stat = sim_one_game(&players);
+        let stat = sim_one_game(&players, &mut rng);
         merge_stats(&mut statistics, &stat);
     }
 
@@ -0,0 +1,307 @@
+// Hosts games on the local machine or network. The browser page is served over plain HTTP on the same port
+// as the WebSocket the page uses to follow the game and send moves. Every page sees the same table,
+// any of them can start a new game and play for the human players.
+//
+// Messages from the page, as JSON :
+// {"type": "new", "players": ["Human", "MctsAI"]}   a new game, with 1 to 4 Human, MctsAI or TdAI players
+// {"type": "play", "move": "U3"}                   a move in notation for the human to play
+// {"type": "undo"}                                 takes back moves until a human is to play again
+//
+// The server replies with {"type": "state", ...} whenever the table changes, and {"type": "error", "message": ...}.
+
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use std::{env, error::Error};
+
+use agricola_game::agricola::{
+    actions::Action,
+    algorithms::{PlayerType, AI},
+    display::{print_farm, print_resources},
+    game::Game,
+    state::MAX_NUM_PLAYERS,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tungstenite::Message;
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:8000";
+const NUM_GAMES_TO_SIMULATE_PER_MOVE: usize = 50;
+const DEPTH: Option<usize> = None;
+const PAGE: &str = include_str!("index.html");
+// Time a client has to send the head of its request
+const HEAD_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Request {
+    New { players: Vec<PlayerType> },
+    Play { r#move: String },
+    Undo,
+}
+
+#[derive(Default)]
+struct Table {
+    game: Option<Game>,
+    // Bumped on every change, so connections know when to send the table again
+    version: u64,
+    thinking: bool,
+}
+
+type Shared = Arc<(Mutex<Table>, Condvar)>;
+
+fn is_ai(player_type: PlayerType) -> bool {
+    matches!(player_type, PlayerType::MctsAI | PlayerType::TdAI)
+}
+
+impl Table {
+    fn changed(&mut self) {
+        self.version += 1;
+    }
+
+    // The AI player to move, if any
+    fn ai_to_move(&self) -> Option<usize> {
+        let state = self.game.as_ref()?.state();
+        let player_idx = self.game.as_ref()?.current_player()?;
+        is_ai(state.player_type(player_idx)).then_some(player_idx)
+    }
+
+    fn handle(&mut self, request: Request) -> Result<(), String> {
+        match request {
+            Request::New { players } => {
+                if players.is_empty() || players.len() > MAX_NUM_PLAYERS {
+                    return Err(format!("Games have 1 to {MAX_NUM_PLAYERS} players"));
+                }
+                if players.contains(&PlayerType::External) {
+                    return Err(String::from("External players are not supported here"));
+                }
+                self.game = Game::new(&players);
+            }
+            Request::Play { r#move } => {
+                let game = self.game.as_mut().ok_or("No game is running")?;
+                let human_to_move = game
+                    .current_player()
+                    .is_some_and(|idx| game.state().player_type(idx) == PlayerType::Human);
+                if !human_to_move {
+                    return Err(String::from("It is not a human player's turn"));
+                }
+                let action = Action::from_notation(&r#move, game.state())?;
+                game.play(&action).map_err(|e| e.to_string())?;
+            }
+            Request::Undo => {
+                let game = self.game.as_mut().ok_or("No game is running")?;
+                while game.undo() {
+                    let state = game.state();
+                    if state.player_type(state.current_player_idx) == PlayerType::Human {
+                        break;
+                    }
+                }
+            }
+        }
+        self.changed();
+        Ok(())
+    }
+
+    fn view(&self) -> Value {
+        let Some(game) = &self.game else {
+            return json!({ "type": "state", "game": null });
+        };
+        let state = game.state();
+        let current_player = game.current_player();
+        let players: Vec<Value> = (0..state.num_players)
+            .map(|idx| {
+                json!({
+                    "type": state.player_type(idx),
+                    "score": state.score(idx),
+                    "resources": print_resources(state, idx),
+                    "farm": print_farm(state.player_farm(idx), state.room_material_idx(idx)),
+                })
+            })
+            .collect();
+        let human_to_move =
+            current_player.is_some_and(|idx| state.player_type(idx) == PlayerType::Human);
+        let moves: Vec<Value> = if human_to_move {
+            game.legal_moves()
+                .iter()
+                .map(|action| json!({ "move": action.notation(), "label": action.describe(state) }))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        json!({
+            "type": "state",
+            "game": {
+                "round": state.current_round,
+                "current_player": current_player,
+                "over": game.is_over(),
+                "thinking": self.thinking,
+                "board": state.format(),
+                "players": players,
+                "moves": moves,
+                "record": game.record(),
+            }
+        })
+    }
+}
+
+// Plays for the AI players, searching with the table unlocked so pages stay responsive
+fn run_ai(shared: &Shared) {
+    let (lock, condvar) = &**shared;
+    let mut ai = AI::new();
+    loop {
+        let (state, version) = {
+            let mut table = lock.lock().unwrap();
+            while table.ai_to_move().is_none() {
+                table = condvar.wait(table).unwrap();
+            }
+            table.thinking = true;
+            table.changed();
+            condvar.notify_all();
+            (table.game.as_ref().unwrap().state().clone(), table.version)
+        };
+
+        let mut records = AI::get_simulation_records(&state);
+        let action = if records.is_empty() {
+            // Only moves the AI never considers are left
+            Action::next_choices(&state)[0].0.clone()
+        } else {
+            for _ in 0..NUM_GAMES_TO_SIMULATE_PER_MOVE * records.len() {
+                ai.sample_once(&mut records, &state, DEPTH);
+            }
+            AI::sort_records(&mut records);
+            records[0].action.clone()
+        };
+        ai.reset();
+
+        let mut table = lock.lock().unwrap();
+        table.thinking = false;
+        // The table may have moved on during the search, a new game or an undo
+        if table.version == version {
+            table.game.as_mut().unwrap().play(&action).unwrap();
+        }
+        table.changed();
+        condvar.notify_all();
+    }
+}
+
+fn send_view(socket: &mut tungstenite::WebSocket<TcpStream>, view: &Value) -> bool {
+    socket.send(Message::Text(view.to_string())).is_ok()
+}
+
+fn serve_socket(stream: TcpStream, shared: &Shared) -> Result<(), Box<dyn Error>> {
+    let (lock, condvar) = &**shared;
+    let mut socket = tungstenite::accept(stream)?;
+    // Reads time out so changes made by other pages and the AI are sent without waiting for this page
+    socket
+        .get_ref()
+        .set_read_timeout(Some(Duration::from_millis(100)))?;
+    let mut version_sent = None;
+    loop {
+        let view = {
+            let table = lock.lock().unwrap();
+            (version_sent != Some(table.version)).then(|| {
+                version_sent = Some(table.version);
+                table.view()
+            })
+        };
+        if let Some(view) = view {
+            if !send_view(&mut socket, &view) {
+                return Ok(());
+            }
+        }
+
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let result = serde_json::from_str::<Request>(&text)
+                    .map_err(|e| e.to_string())
+                    .and_then(|request| lock.lock().unwrap().handle(request));
+                condvar.notify_all();
+                if let Err(message) = result {
+                    let error = json!({ "type": "error", "message": message });
+                    if !send_view(&mut socket, &error) {
+                        return Ok(());
+                    }
+                }
+            }
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+// Reads the bytes of the request head without consuming them, so a WebSocket handshake can still read them.
+// Fails once HEAD_TIMEOUT has passed, so a client that stops sending does not hold its thread forever.
+fn peek_head(stream: &TcpStream) -> io::Result<Vec<u8>> {
+    let deadline = Instant::now() + HEAD_TIMEOUT;
+    stream.set_read_timeout(Some(HEAD_TIMEOUT))?;
+    let mut buf = [0; 4096];
+    loop {
+        let n = stream.peek(&mut buf)?;
+        if buf[..n].windows(4).any(|w| w == b"\r\n\r\n") || n == buf.len() || n == 0 {
+            return Ok(buf[..n].to_vec());
+        }
+        if Instant::now() >= deadline {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "The request head was not sent in time",
+            ));
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+fn serve_connection(mut stream: TcpStream, shared: &Shared) -> Result<(), Box<dyn Error>> {
+    let peeked = peek_head(&stream)?;
+    // Only for matching, invalid bytes are replaced so the text may be longer than what was peeked
+    let head = String::from_utf8_lossy(&peeked);
+    if head.to_lowercase().contains("upgrade: websocket") {
+        return serve_socket(stream, shared);
+    }
+
+    let mut buf = vec![0; peeked.len()];
+    stream.read_exact(&mut buf)?;
+    let response = if head.starts_with("GET / ") {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{PAGE}",
+            PAGE.len()
+        )
+    } else {
+        String::from("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+    };
+    stream.write_all(response.as_bytes())?;
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // Pass an address such as 0.0.0.0:8000 to play over the local network
+    let address = env::args()
+        .nth(1)
+        .unwrap_or_else(|| String::from(DEFAULT_ADDRESS));
+    let listener = TcpListener::bind(&address)?;
+    println!("Open http://{address} in a browser");
+
+    let shared: Shared = Arc::new((Mutex::new(Table::default()), Condvar::new()));
+    let ai_shared = Arc::clone(&shared);
+    thread::spawn(move || run_ai(&ai_shared));
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let shared = Arc::clone(&shared);
+        thread::spawn(move || {
+            if let Err(e) = serve_connection(stream, &shared) {
+                println!("Connection closed : {e}");
+            }
+        });
+    }
+    Ok(())
+}